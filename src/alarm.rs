@@ -0,0 +1,359 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Recurring Alarms
+//!
+//! Alarms are kept in the config file (see `config`) and rung by `clock daemon`:
+//!
+//! ```toml
+//! [[alarms]]
+//! name = "standup"
+//! schedule = "weekdays 09:55"
+//!
+//! [[alarms]]
+//! name = "stretch"
+//! schedule = "every 2h from 09:00 to 17:00"
+//! enabled = false
+//! ```
+//!
+//! A schedule is either a time of day, optionally preceded by the days it rings on (`daily`,
+//! `weekdays`, `weekends`, or days and ranges such as `mon,wed,fri` or `mon-thu`), or `every`
+//! followed by an interval, optionally starting `from` a time of day (midnight by default) and
+//! ending at a time of day (`to`). An interval restarts from its starting time every day.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// When an alarm rings, written like `weekdays 09:55` or `every 2h`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Schedule {
+    /// At `time` on the days which are set, Monday first
+    Weekly { days: [bool; 7], time: NaiveTime },
+    /// Every `interval`, starting at `from` each day, until `to` if set
+    Every {
+        interval: Duration,
+        from: NaiveTime,
+        to: Option<NaiveTime>,
+    },
+}
+
+impl Schedule {
+    /// The first time the schedule rings after `t`
+    pub fn next_after(&self, t: DateTime<Local>) -> Option<DateTime<Local>> {
+        let today = t.date_naive();
+        match self {
+            Schedule::Weekly { days, time } => (0..=7)
+                .map(|i| today + Duration::days(i))
+                .filter(|date| days[date.weekday().num_days_from_monday() as usize])
+                .filter_map(|date| local(date, *time))
+                .find(|&ring| ring > t),
+            Schedule::Every { interval, from, to } => {
+                let mut start = local(today, *from)?;
+                if start > t {
+                    start = local(today - Duration::days(1), *from)?;
+                }
+                let next_start = local(start.date_naive() + Duration::days(1), *from)?;
+                let n = (t - start).num_milliseconds() / interval.num_milliseconds() + 1;
+                let ring = start + *interval * n as i32;
+                let end = match to {
+                    // `to` is on the next day if it is earlier than `from`, e.g. 22:00 to 02:00
+                    Some(to) if to < from => local(start.date_naive() + Duration::days(1), *to),
+                    Some(to) => local(start.date_naive(), *to),
+                    None => None,
+                };
+                if ring >= next_start || end.map_or(false, |end| ring > end) {
+                    Some(next_start)
+                } else {
+                    Some(ring)
+                }
+            }
+        }
+    }
+}
+
+/// `date` at `time` in the local time zone, if that time exists (e.g. not skipped for DST)
+fn local(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| format!("invalid time: {}", s))
+}
+
+fn parse_day(s: &str) -> Result<usize, String> {
+    let s = s.to_lowercase();
+    DAY_NAMES
+        .iter()
+        .position(|&day| s.len() >= 3 && day.starts_with(&s[..3]))
+        .ok_or_else(|| format!("invalid day: {}", s))
+}
+
+fn parse_days(s: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    match s {
+        "daily" | "day" => days = [true; 7],
+        "weekdays" => days[..5].copy_from_slice(&[true; 5]),
+        "weekends" => days[5..].copy_from_slice(&[true; 2]),
+        _ => {
+            for part in s.split(',') {
+                match part.split_once('-') {
+                    Some((first, last)) => {
+                        let (first, last) = (parse_day(first)?, parse_day(last)?);
+                        // ranges may wrap around, e.g. fri-mon
+                        let mut day = first;
+                        loop {
+                            days[day] = true;
+                            if day == last {
+                                break;
+                            }
+                            day = (day + 1) % 7;
+                        }
+                    }
+                    None => days[parse_day(part)?] = true,
+                }
+            }
+        }
+    }
+    Ok(days)
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let interval = s
+            .strip_prefix("every ")
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+        if let Some(rest) = interval {
+            let (rest, to) = match rest.split_once(" to ") {
+                Some((rest, to)) => (rest, Some(parse_time(to.trim())?)),
+                None => (rest, None),
+            };
+            let (interval, from) = match rest.split_once(" from ") {
+                Some((interval, from)) => (interval, parse_time(from.trim())?),
+                None => (rest, NaiveTime::from_hms_opt(0, 0, 0).unwrap()),
+            };
+            let interval = interval
+                .trim()
+                .parse::<humantime::Duration>()
+                .map_err(|e| e.to_string())?;
+            let interval = Duration::from_std(interval.into()).map_err(|e| e.to_string())?;
+            if interval < Duration::minutes(1) {
+                return Err("the interval must be at least a minute".to_owned());
+            }
+            return Ok(Schedule::Every { interval, from, to });
+        }
+        let (days, time) = match s.rsplit_once(' ') {
+            Some((days, time)) => (parse_days(days.trim().trim_start_matches("every "))?, time),
+            None => ([true; 7], s),
+        };
+        Ok(Schedule::Weekly {
+            days,
+            time: parse_time(time)?,
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schedule::Weekly { days, time } => {
+                let days = match days {
+                    [true, true, true, true, true, true, true] => "daily".to_owned(),
+                    [true, true, true, true, true, false, false] => "weekdays".to_owned(),
+                    [false, false, false, false, false, true, true] => "weekends".to_owned(),
+                    _ => DAY_NAMES
+                        .iter()
+                        .zip(days.iter())
+                        .filter(|(_, &set)| set)
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                };
+                write!(f, "{} {}", days, time.format("%H:%M"))
+            }
+            Schedule::Every { interval, from, to } => {
+                let interval = humantime::format_duration(interval.to_std().unwrap());
+                write!(f, "every {}", interval)?;
+                if *from != NaiveTime::from_hms_opt(0, 0, 0).unwrap() {
+                    write!(f, " from {}", from.format("%H:%M"))?;
+                }
+                if let Some(to) = to {
+                    write!(f, " to {}", to.format("%H:%M"))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.to_string()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Alarm {
+    pub name: String,
+    pub schedule: Schedule,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// When the snoozed alarm rings again. The schedule does not ring before then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Local>>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Alarm {
+    pub fn new(name: String, schedule: Schedule) -> Self {
+        Self {
+            name,
+            schedule,
+            enabled: true,
+            snoozed_until: None,
+        }
+    }
+
+    /// The first time the alarm rings after `t`, unless it is disabled
+    pub fn next_ring(&self, t: DateTime<Local>) -> Option<DateTime<Local>> {
+        if !self.enabled {
+            return None;
+        }
+        match self.snoozed_until {
+            Some(snoozed_until) if snoozed_until > t => Some(snoozed_until),
+            _ => self.schedule.next_after(t),
+        }
+    }
+
+    /// Whether the alarm rings after `after` and no later than `until`
+    pub fn rings_between(&self, after: DateTime<Local>, until: DateTime<Local>) -> bool {
+        self.next_ring(after).map_or(false, |ring| ring <= until)
+    }
+
+    /// Makes the alarm ring `length` after it last rang (or was snoozed to) if that was less than
+    /// `length` ago, and otherwise `length` after it next rings
+    pub fn snooze(&mut self, length: Duration, now: DateTime<Local>) {
+        let base = match self.snoozed_until {
+            Some(snoozed_until) if snoozed_until > now - length => Some(snoozed_until),
+            _ => self.schedule.next_after(now - length),
+        };
+        self.snoozed_until = base.map(|base| base + length);
+        self.enabled = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A time on 2020-10-12 (a Monday) plus `days`
+    fn at(days: u32, h: u32, m: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2020, 10, 12 + days, h, m, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    fn schedule(s: &str) -> Schedule {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for s in &[
+            "daily 07:30",
+            "weekdays 09:55",
+            "weekends 10:00",
+            "mon,wed,fri 18:00",
+            "every 2h",
+            "every 30m from 09:00",
+            "every 2h from 09:00 to 17:00",
+        ] {
+            assert_eq!(schedule(s).to_string(), *s);
+        }
+        assert_eq!(schedule("07:30"), schedule("daily 07:30"));
+        assert_eq!(schedule("every mon-fri 09:55"), schedule("weekdays 09:55"));
+        assert_eq!(
+            schedule("fri-mon 08:00").to_string(),
+            "mon,fri,sat,sun 08:00"
+        );
+        assert!("every 30s".parse::<Schedule>().is_err());
+        assert!("mon-xyz 08:00".parse::<Schedule>().is_err());
+        assert!("weekdays 25:00".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn weekly() {
+        let weekdays = schedule("weekdays 09:55");
+        assert_eq!(weekdays.next_after(at(0, 8, 0)), Some(at(0, 9, 55)));
+        assert_eq!(weekdays.next_after(at(0, 9, 55)), Some(at(1, 9, 55)));
+        // from Friday evening to Monday
+        assert_eq!(weekdays.next_after(at(4, 18, 0)), Some(at(7, 9, 55)));
+        let sunday = schedule("sun 12:00");
+        assert_eq!(sunday.next_after(at(6, 12, 0)), Some(at(13, 12, 0)));
+    }
+
+    #[test]
+    fn every() {
+        let every = schedule("every 2h from 09:00 to 17:00");
+        assert_eq!(every.next_after(at(0, 6, 0)), Some(at(0, 9, 0)));
+        assert_eq!(every.next_after(at(0, 9, 0)), Some(at(0, 11, 0)));
+        assert_eq!(every.next_after(at(0, 12, 30)), Some(at(0, 13, 0)));
+        assert_eq!(every.next_after(at(0, 16, 0)), Some(at(0, 17, 0)));
+        assert_eq!(every.next_after(at(0, 17, 0)), Some(at(1, 9, 0)));
+        // overnight, from 22:00 to 02:00 the next day
+        let overnight = schedule("every 1h from 22:00 to 02:00");
+        assert_eq!(overnight.next_after(at(0, 23, 30)), Some(at(1, 0, 0)));
+        assert_eq!(overnight.next_after(at(1, 1, 30)), Some(at(1, 2, 0)));
+        assert_eq!(overnight.next_after(at(1, 2, 0)), Some(at(1, 22, 0)));
+        // restarts from `from` every day
+        let every = schedule("every 5h");
+        assert_eq!(every.next_after(at(0, 20, 30)), Some(at(1, 0, 0)));
+    }
+
+    #[test]
+    fn snooze() {
+        let mut alarm = Alarm::new("standup".to_owned(), schedule("daily 09:00"));
+        alarm.snooze(Duration::minutes(10), at(0, 9, 2));
+        assert_eq!(alarm.next_ring(at(0, 9, 2)), Some(at(0, 9, 10)));
+        alarm.snooze(Duration::minutes(10), at(0, 9, 11));
+        assert_eq!(alarm.next_ring(at(0, 9, 11)), Some(at(0, 9, 20)));
+        assert!(alarm.rings_between(at(0, 9, 11), at(0, 9, 20)));
+        assert_eq!(alarm.next_ring(at(0, 9, 20)), Some(at(1, 9, 0)));
+        alarm.enabled = false;
+        assert_eq!(alarm.next_ring(at(0, 8, 0)), None);
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Configuration
+//!
+//! Settings kept in `config.toml` in the config directory (see `utils::config_dir`). These are the time zones of the
+//! world clock (see `world`) and the recurring alarms (see `alarm`).
+
+use crate::alarm::Alarm;
+use crate::utils::{config_dir, BoxedError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(skip)]
+    path: PathBuf,
+    /// The time zones shown by `clock world`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alarms: Vec<Alarm>,
+}
+
+impl Config {
+    /// The default config file
+    pub fn default_path() -> PathBuf {
+        config_dir().join("config.toml")
+    }
+
+    /// Opens the default config file
+    pub fn open() -> Result<Self, BoxedError> {
+        Self::open_at(Self::default_path())
+    }
+
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self, BoxedError> {
+        let path = path.as_ref().to_owned();
+        let mut config: Self = if path.exists() {
+            toml::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+        } else {
+            Self::default()
+        };
+        config.path = path;
+        Ok(config)
+    }
+
+    pub fn alarm(&self, name: &str) -> Option<&Alarm> {
+        self.alarms.iter().find(|a| a.name == name)
+    }
+
+    /// Adds an alarm, replacing the one with the same name if there is one
+    pub fn add_alarm(&mut self, alarm: Alarm) -> Result<(), BoxedError> {
+        match self.alarms.iter_mut().find(|a| a.name == alarm.name) {
+            Some(existing) => *existing = alarm,
+            None => self.alarms.push(alarm),
+        }
+        self.save()
+    }
+
+    pub fn remove_alarm(&mut self, name: &str) -> Result<(), BoxedError> {
+        let len = self.alarms.len();
+        self.alarms.retain(|a| a.name != name);
+        if self.alarms.len() == len {
+            return Err(format!("no alarm named {}", name).into());
+        }
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<(), BoxedError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
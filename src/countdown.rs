@@ -0,0 +1,202 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Countdowns to Events
+//!
+//! Named events (release dates, holidays, ...) kept in `events.toml` in the data directory:
+//!
+//! ```toml
+//! [[events]]
+//! name = "New Year"
+//! target = "2021-01-01T00:00:00+08:00"
+//! ```
+
+use crate::utils::{data_dir, parse_datetime, BoxedError};
+use chrono::{DateTime, Duration, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CountdownEvent {
+    pub name: String,
+    pub target: DateTime<Local>,
+}
+
+impl CountdownEvent {
+    /// The time left at `now` until the event, which is negative once it has passed
+    pub fn remaining(&self, now: DateTime<Local>) -> Duration {
+        self.target - now
+    }
+}
+
+/// Parses the target of a countdown: a date/time (see `parse_datetime`), or a duration from `now`
+/// such as `40 days`, to the second
+pub fn parse_target(s: &str, now: DateTime<Local>) -> Result<DateTime<Local>, String> {
+    let duration = || {
+        let duration = s.trim().parse::<humantime::Duration>().ok()?;
+        Duration::from_std(duration.into()).ok()
+    };
+    parse_datetime(s)
+        .ok()
+        .or_else(|| Some(now.with_nanosecond(0)? + duration()?))
+        .ok_or_else(|| format!("expected a date/time or a duration: {}", s))
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EventStore {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    events: Vec<CountdownEvent>,
+}
+
+impl EventStore {
+    /// Opens the default store in the data directory
+    pub fn open() -> Result<Self, BoxedError> {
+        Self::open_at(data_dir().join("events.toml"))
+    }
+
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self, BoxedError> {
+        let path = path.as_ref().to_owned();
+        let mut store: Self = if path.exists() {
+            toml::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+        } else {
+            Self::default()
+        };
+        store.path = path;
+        Ok(store)
+    }
+
+    /// All events, the nearest first
+    pub fn events(&self) -> &[CountdownEvent] {
+        &self.events
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CountdownEvent> {
+        self.events.iter().find(|e| e.name == name)
+    }
+
+    /// Adds an event, replacing the one with the same name if there is one
+    pub fn add(&mut self, event: CountdownEvent) -> Result<(), BoxedError> {
+        self.events.retain(|e| e.name != event.name);
+        self.events.push(event);
+        self.events.sort_by_key(|e| e.target);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), BoxedError> {
+        let len = self.events.len();
+        self.events.retain(|e| e.name != name);
+        if self.events.len() == len {
+            return Err(format!("no event named {}", name).into());
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), BoxedError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    fn event(name: &str, target: DateTime<Local>) -> CountdownEvent {
+        CountdownEvent {
+            name: name.to_owned(),
+            target,
+        }
+    }
+
+    #[test]
+    fn targets() {
+        let now = local(2020, 10, 12, 9, 30) + Duration::milliseconds(1500);
+        assert_eq!(parse_target("2021-01-01", now), Ok(local(2021, 1, 1, 0, 0)));
+        assert_eq!(
+            parse_target("2021-02-12 18:00", now),
+            Ok(local(2021, 2, 12, 18, 0))
+        );
+        // in the past, which counts up instead
+        assert_eq!(parse_target("2020-01-01", now), Ok(local(2020, 1, 1, 0, 0)));
+        assert_eq!(
+            parse_target("40 days", now),
+            Ok(local(2020, 11, 21, 9, 30) + Duration::seconds(1))
+        );
+        assert_eq!(
+            parse_target(" 1h 30m ", now),
+            Ok(local(2020, 10, 12, 11, 0) + Duration::seconds(1))
+        );
+        assert!(parse_target("next week", now).is_err());
+        assert!(parse_target("2021-13-01", now).is_err());
+    }
+
+    #[test]
+    fn remaining() {
+        let now = local(2020, 10, 12, 9, 30);
+        let new_year = event("New Year", local(2021, 1, 1, 0, 0));
+        assert_eq!(
+            new_year.remaining(now),
+            Duration::days(80) + Duration::minutes(14 * 60 + 30)
+        );
+        assert_eq!(new_year.remaining(new_year.target), Duration::zero());
+        let passed = event("Launch", local(2020, 10, 11, 9, 0));
+        assert_eq!(passed.remaining(now), -Duration::minutes(24 * 60 + 30));
+    }
+
+    #[test]
+    fn store() {
+        let dir = std::env::temp_dir().join(format!("clock-cli-test-{}", std::process::id()));
+        let path = dir.join("events.toml");
+        let _ = fs::remove_file(&path);
+        let mut store = EventStore::open_at(&path).unwrap();
+        store
+            .add(event("New Year", local(2021, 1, 1, 0, 0)))
+            .unwrap();
+        store
+            .add(event("Launch", local(2020, 11, 1, 9, 0)))
+            .unwrap();
+        store
+            .add(event("New Year", local(2021, 1, 1, 8, 0)))
+            .unwrap();
+        assert!(store.remove("Halloween").is_err());
+
+        let mut store = EventStore::open_at(&path).unwrap();
+        let names: Vec<&str> = store.events().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Launch", "New Year"]);
+        assert_eq!(
+            store.get("New Year").map(|e| e.target),
+            Some(local(2021, 1, 1, 8, 0))
+        );
+        store.remove("Launch").unwrap();
+        assert_eq!(EventStore::open_at(&path).unwrap().events().len(), 1);
+    }
+}
@@ -0,0 +1,597 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Daemon
+//!
+//! `clock daemon` keeps running in the background. It owns the timers and stopwatches started with
+//! `--detach`, and sends a notification when a timer finishes or an alarm in the config file (see
+//! `alarm`) rings, whether a TUI is open or not. The config file is read again whenever it
+//! changes, so alarms added, removed, enabled, disabled or snoozed with `clock alarm` take effect
+//! right away.
+//!
+//! Clients talk to the daemon through a Unix socket (`daemon.sock` in the data directory). Each
+//! connection sends one `Request` and receives one `Response`, as a line of JSON each.
+
+use crate::alarm::Alarm;
+use crate::config::Config;
+use crate::notify::notify;
+use crate::track::{Lap, TimerRun};
+use crate::utils::{data_dir, dhhmmss, duration_ms, BoxedError};
+use chrono::{Duration, Local};
+use clock_core::stopwatch::Stopwatch;
+use clock_core::timer::{Timer, TimerData};
+use hhmmss::Hhmmss;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    StartTimer {
+        #[serde(with = "duration_ms")]
+        duration: Duration,
+    },
+    StartStopwatch,
+    PauseOrResume {
+        id: u32,
+    },
+    Lap {
+        id: u32,
+    },
+    /// Stops a stopwatch, or cancels a timer
+    Stop {
+        id: u32,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Started {
+        id: u32,
+    },
+    Status(Status),
+    /// The last state of a stopped job
+    Stopped {
+        job: Job,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Everything the daemon owns
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Status {
+    pub jobs: Vec<Job>,
+    pub alarms: Vec<Alarm>,
+}
+
+/// A timer or stopwatch owned by the daemon
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Job {
+    Timer {
+        id: u32,
+        #[serde(with = "duration_ms")]
+        total: Duration,
+        #[serde(with = "duration_ms")]
+        remaining: Duration,
+        paused: bool,
+    },
+    Stopwatch {
+        id: u32,
+        #[serde(with = "duration_ms")]
+        elapsed: Duration,
+        laps: Vec<Lap>,
+        paused: bool,
+    },
+}
+
+impl Job {
+    pub fn id(&self) -> u32 {
+        match self {
+            Job::Timer { id, .. } | Job::Stopwatch { id, .. } => *id,
+        }
+    }
+
+    pub fn paused(&self) -> bool {
+        match self {
+            Job::Timer { paused, .. } | Job::Stopwatch { paused, .. } => *paused,
+        }
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Job::Timer {
+                id,
+                total,
+                remaining,
+                ..
+            } => write!(
+                f,
+                "#{:<3} timer      {:>12} of {}",
+                id,
+                dhhmmss(*remaining),
+                dhhmmss(*total)
+            )?,
+            Job::Stopwatch {
+                id, elapsed, laps, ..
+            } => write!(
+                f,
+                "#{:<3} stopwatch  {:>12}  {} laps",
+                id,
+                elapsed.hhmmssxxx(),
+                laps.len()
+            )?,
+        }
+        if self.paused() {
+            write!(f, "  paused")?;
+        }
+        Ok(())
+    }
+}
+
+/// The socket the daemon listens on
+pub fn socket_path() -> PathBuf {
+    data_dir().join("daemon.sock")
+}
+
+/// Sends a request to the daemon. An error response is returned as an error.
+pub fn request(request: &Request) -> Result<Response, BoxedError> {
+    let mut stream = UnixStream::connect(socket_path()).map_err(|e| {
+        format!(
+            "the daemon is not running ({}); start it with `clock daemon`",
+            e
+        )
+    })?;
+    writeln!(stream, "{}", serde_json::to_string(request)?)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    match serde_json::from_str(&line)? {
+        Response::Error { message } => Err(message.into()),
+        response => Ok(response),
+    }
+}
+
+/// Starts `clock daemon` in the background, in a new session so that it keeps running when the
+/// terminal is closed, unless it is already running
+pub fn spawn() -> Result<(), BoxedError> {
+    if UnixStream::connect(socket_path()).is_ok() {
+        return Ok(());
+    }
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg("daemon")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid() is async-signal-safe, so it may be called between fork() and exec()
+    unsafe {
+        command.pre_exec(|| match libc::setsid() {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        });
+    }
+    command.spawn()?;
+    for _ in 0..50 {
+        if UnixStream::connect(socket_path()).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(std::time::Duration::from_millis(100));
+    }
+    Err("the daemon did not start".into())
+}
+
+enum Owned {
+    Timer { timer: Timer, paused: bool },
+    Stopwatch { stopwatch: Stopwatch, paused: bool },
+}
+
+#[derive(Default)]
+struct Daemon {
+    jobs: Vec<(u32, Owned)>,
+    next_id: u32,
+    alarms: Vec<Alarm>,
+}
+
+impl Daemon {
+    fn add(&mut self, job: Owned) -> Response {
+        self.next_id += 1;
+        self.jobs.push((self.next_id, job));
+        Response::Started { id: self.next_id }
+    }
+
+    fn job(&mut self, id: u32) -> Result<&mut Owned, Response> {
+        match self.jobs.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, job)) => Ok(job),
+            None => Err(Response::Error {
+                message: format!("no timer or stopwatch #{}", id),
+            }),
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> Response {
+        let id = match request {
+            Request::Status => return Response::Status(self.status()),
+            Request::StartTimer { duration } => {
+                let mut timer = Timer::new(duration);
+                timer.pause_or_resume();
+                return self.add(Owned::Timer {
+                    timer,
+                    paused: false,
+                });
+            }
+            Request::StartStopwatch => {
+                let mut stopwatch = Stopwatch::default();
+                stopwatch.pause_or_resume();
+                return self.add(Owned::Stopwatch {
+                    stopwatch,
+                    paused: false,
+                });
+            }
+            Request::PauseOrResume { id } | Request::Lap { id } | Request::Stop { id } => id,
+        };
+        let job = match self.job(id) {
+            Ok(job) => job,
+            Err(response) => return response,
+        };
+        match (request, job) {
+            (Request::PauseOrResume { .. }, Owned::Timer { timer, paused }) => {
+                timer.pause_or_resume();
+                *paused = !*paused;
+            }
+            (Request::PauseOrResume { .. }, Owned::Stopwatch { stopwatch, paused }) => {
+                stopwatch.pause_or_resume();
+                *paused = !*paused;
+            }
+            (Request::Lap { .. }, Owned::Stopwatch { stopwatch, .. }) => {
+                stopwatch.lap();
+            }
+            (Request::Lap { .. }, Owned::Timer { .. }) => {
+                return Response::Error {
+                    message: format!("#{} is a timer", id),
+                }
+            }
+            // stop
+            (_, job) => {
+                let last = snapshot(id, job);
+                if let Owned::Timer { timer, .. } = job {
+                    let _ = TimerRun::new(&timer.stop(), false).record();
+                }
+                self.jobs.retain(|(i, _)| *i != id);
+                return Response::Stopped { job: last };
+            }
+        }
+        Response::Ok
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            jobs: self
+                .jobs
+                .iter()
+                .map(|(id, job)| snapshot(*id, job))
+                .collect(),
+            alarms: self.alarms.clone(),
+        }
+    }
+
+    /// Removes the timers which have counted to zero, and returns their ids and data
+    fn finish_timers(&mut self) -> Vec<(u32, TimerData)> {
+        let finished: Vec<u32> = self
+            .jobs
+            .iter()
+            .filter_map(|(id, job)| match job {
+                Owned::Timer { timer, paused }
+                    if !paused && timer.read() < Duration::milliseconds(10) =>
+                {
+                    Some(*id)
+                }
+                _ => None,
+            })
+            .collect();
+        let mut data = Vec::new();
+        for id in finished {
+            if let Owned::Timer { timer, .. } = job_mut(&mut self.jobs, id) {
+                data.push((id, timer.stop()));
+            }
+            self.jobs.retain(|(i, _)| *i != id);
+        }
+        data
+    }
+}
+
+fn job_mut(jobs: &mut [(u32, Owned)], id: u32) -> &mut Owned {
+    &mut jobs.iter_mut().find(|(i, _)| *i == id).unwrap().1
+}
+
+fn snapshot(id: u32, job: &Owned) -> Job {
+    match job {
+        Owned::Timer { timer, paused } => Job::Timer {
+            id,
+            total: timer.data.total,
+            remaining: timer.read(),
+            paused: *paused,
+        },
+        Owned::Stopwatch { stopwatch, paused } => Job::Stopwatch {
+            id,
+            elapsed: stopwatch.read(),
+            laps: stopwatch
+                .data
+                .laps
+                .iter()
+                .map(|&time| Lap { time, label: None })
+                .collect(),
+            paused: *paused,
+        },
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn serve(daemon: &Mutex<Daemon>, stream: UnixStream) -> Result<(), BoxedError> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response = match serde_json::from_str(&line) {
+        Ok(request) => daemon.lock().unwrap().handle(request),
+        Err(e) => Response::Error {
+            message: format!("invalid request: {}", e),
+        },
+    };
+    writeln!(&stream, "{}", serde_json::to_string(&response)?)?;
+    Ok(())
+}
+
+/// Runs the daemon until the process is killed
+pub fn run() -> Result<(), BoxedError> {
+    let socket = socket_path();
+    if UnixStream::connect(&socket).is_ok() {
+        return Err("the daemon is already running".into());
+    }
+    if let Some(dir) = socket.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // left behind by a daemon which did not exit cleanly
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+
+    let path = Config::default_path();
+    let mut config_modified = modified(&path);
+    let daemon = Arc::new(Mutex::new(Daemon {
+        alarms: Config::open_at(&path)?.alarms,
+        ..Daemon::default()
+    }));
+
+    let server = daemon.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = serve(&server, stream);
+        }
+    });
+
+    let mut last_check = Local::now();
+    loop {
+        thread::sleep(std::time::Duration::from_millis(200));
+        // notifications are sent once the lock is released, so that they don't hold up clients
+        let (rings, finished) = {
+            let mut daemon = daemon.lock().unwrap();
+            if modified(&path) != config_modified {
+                config_modified = modified(&path);
+                match Config::open_at(&path) {
+                    Ok(config) => daemon.alarms = config.alarms,
+                    Err(e) => eprintln!("Failed to reload the config file: {}", e),
+                }
+            }
+            let now = Local::now();
+            let rings: Vec<String> = daemon
+                .alarms
+                .iter()
+                .filter(|alarm| alarm.rings_between(last_check, now))
+                .map(|alarm| format!("{} ({})", alarm.name, alarm.schedule))
+                .collect();
+            last_check = now;
+            (rings, daemon.finish_timers())
+        };
+        for message in rings {
+            let _ = notify(&message);
+        }
+        for (id, data) in finished {
+            let _ = TimerRun::new(&data, true).record();
+            let _ = notify(&format!(
+                "Timer #{} finished\nExpected: {}\nActual: {}",
+                id,
+                dhhmmss(data.duration_expected()),
+                dhhmmss(data.duration_actual())
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+
+    /// The JSON of `value`, after checking that it reads back the same
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> String {
+        let json = serde_json::to_string(value).unwrap();
+        let read: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+        json
+    }
+
+    #[test]
+    fn requests() {
+        assert_eq!(round_trip(&Request::Status), r#"{"request":"status"}"#);
+        assert_eq!(
+            round_trip(&Request::StartTimer {
+                duration: Duration::seconds(90)
+            }),
+            r#"{"request":"start_timer","duration":90000}"#
+        );
+        assert_eq!(
+            round_trip(&Request::StartStopwatch),
+            r#"{"request":"start_stopwatch"}"#
+        );
+        assert_eq!(
+            round_trip(&Request::PauseOrResume { id: 2 }),
+            r#"{"request":"pause_or_resume","id":2}"#
+        );
+        assert_eq!(
+            round_trip(&Request::Lap { id: 2 }),
+            r#"{"request":"lap","id":2}"#
+        );
+        assert_eq!(
+            round_trip(&Request::Stop { id: 3 }),
+            r#"{"request":"stop","id":3}"#
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"request":"restart"}"#).is_err());
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(round_trip(&Response::Ok), r#"{"response":"ok"}"#);
+        assert_eq!(
+            round_trip(&Response::Started { id: 1 }),
+            r#"{"response":"started","id":1}"#
+        );
+        assert_eq!(
+            round_trip(&Response::Error {
+                message: "no timer or stopwatch #4".to_owned()
+            }),
+            r#"{"response":"error","message":"no timer or stopwatch #4"}"#
+        );
+        let stopwatch = Job::Stopwatch {
+            id: 2,
+            elapsed: Duration::milliseconds(61_500),
+            laps: vec![Lap {
+                time: Duration::seconds(30),
+                label: None,
+            }],
+            paused: true,
+        };
+        assert_eq!(
+            round_trip(&Response::Stopped {
+                job: stopwatch.clone()
+            }),
+            r#"{"response":"stopped","job":{"kind":"stopwatch","id":2,"elapsed":61500,"laps":[{"time":30000}],"paused":true}}"#
+        );
+        let timer = Job::Timer {
+            id: 1,
+            total: Duration::minutes(5),
+            remaining: Duration::minutes(4),
+            paused: false,
+        };
+        let status = Status {
+            jobs: vec![timer, stopwatch],
+            alarms: vec![Alarm::new(
+                "standup".to_owned(),
+                "weekdays 09:55".parse().unwrap(),
+            )],
+        };
+        let json = round_trip(&Response::Status(status));
+        assert!(json.starts_with(
+            r#"{"response":"status","jobs":[{"kind":"timer","id":1,"total":300000,"remaining":240000,"paused":false},"#
+        ));
+        assert!(json.contains(r#""schedule":"weekdays 09:55""#));
+    }
+
+    fn start_timer(daemon: &mut Daemon, duration: Duration) -> u32 {
+        match daemon.handle(Request::StartTimer { duration }) {
+            Response::Started { id } => id,
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn jobs() {
+        let mut daemon = Daemon::default();
+        assert_eq!(start_timer(&mut daemon, Duration::hours(1)), 1);
+        assert!(matches!(
+            daemon.handle(Request::StartStopwatch),
+            Response::Started { id: 2 }
+        ));
+        assert!(matches!(
+            daemon.handle(Request::Lap { id: 2 }),
+            Response::Ok
+        ));
+        assert!(matches!(
+            daemon.handle(Request::PauseOrResume { id: 1 }),
+            Response::Ok
+        ));
+        assert!(matches!(
+            daemon.handle(Request::Lap { id: 1 }),
+            Response::Error { .. }
+        ));
+        assert!(matches!(
+            daemon.handle(Request::Stop { id: 7 }),
+            Response::Error { .. }
+        ));
+
+        let status = daemon.status();
+        assert_eq!(status.jobs.len(), 2);
+        assert!(status.jobs[0].paused());
+        assert!(!status.jobs[1].paused());
+        match daemon.handle(Request::Stop { id: 2 }) {
+            Response::Stopped {
+                job: Job::Stopwatch { id: 2, laps, .. },
+            } => assert_eq!(laps.len(), 1),
+            response => panic!("unexpected response: {:?}", response),
+        }
+        assert_eq!(daemon.status().jobs.len(), 1);
+        // ids are not reused
+        assert_eq!(start_timer(&mut daemon, Duration::hours(1)), 3);
+    }
+
+    #[test]
+    fn finish_timers() {
+        let mut daemon = Daemon::default();
+        let finishing = start_timer(&mut daemon, Duration::milliseconds(5));
+        let paused = start_timer(&mut daemon, Duration::milliseconds(5));
+        daemon.handle(Request::PauseOrResume { id: paused });
+        let running = start_timer(&mut daemon, Duration::hours(1));
+        daemon.handle(Request::StartStopwatch);
+        thread::sleep(std::time::Duration::from_millis(20));
+
+        let finished = daemon.finish_timers();
+        assert_eq!(finished.len(), 1);
+        let (id, data) = &finished[0];
+        assert_eq!(*id, finishing);
+        assert_eq!(data.duration_expected(), Duration::milliseconds(5));
+        assert!(data.duration_actual() >= Duration::milliseconds(5));
+
+        let ids: Vec<u32> = daemon.status().jobs.iter().map(Job::id).collect();
+        assert_eq!(ids, vec![paused, running, 4]);
+        assert!(daemon.finish_timers().is_empty());
+    }
+}
@@ -0,0 +1,637 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Export and Import
+//!
+//! Converts sessions to and from:
+//!
+//! - iCalendar (`.ics`): one `VEVENT` per session. The tracked time and the laps are kept in
+//!   `X-CLOCK-ELAPSED` and `X-CLOCK-LAP` properties, so the round trip is lossless.
+//! - Org-mode: one heading per project and tags, with a `CLOCK: [..]--[..] => h:mm` line per
+//!   session. Org only keeps minutes, and tags may only contain letters, digits, `_`, `@`, `#` and
+//!   `%`.
+//! - timewarrior's data format (`inc <start> - <end> # <tags>`), with the project as the first tag.
+//!
+//! The tracked time of a session imported from Org-mode or timewarrior is the time between its
+//! start and end, and laps are lost.
+
+use crate::track::{Lap, Session};
+use crate::utils::BoxedError;
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use hhmmss::Hhmmss;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    ICalendar,
+    Org,
+    Timewarrior,
+}
+
+impl Format {
+    /// Guesses the format from a file name, e.g. `sessions.ics` or `2020-10.data`
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit('.').next()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "ics" => Some(Format::ICalendar),
+            "org" => Some(Format::Org),
+            "data" => Some(Format::Timewarrior),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ics" | "ical" | "icalendar" => Ok(Format::ICalendar),
+            "org" => Ok(Format::Org),
+            "timewarrior" | "timew" => Ok(Format::Timewarrior),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+pub fn export(sessions: &[Session], format: Format) -> String {
+    match format {
+        Format::ICalendar => to_ics(sessions),
+        Format::Org => to_org(sessions),
+        Format::Timewarrior => to_timewarrior(sessions),
+    }
+}
+
+/// Reads sessions. Their ids are 0 until they are added to a `SessionStore`.
+pub fn import(content: &str, format: Format) -> Result<Vec<Session>, BoxedError> {
+    match format {
+        Format::ICalendar => from_ics(content),
+        Format::Org => from_org(content),
+        Format::Timewarrior => from_timewarrior(content),
+    }
+}
+
+fn new_session(
+    project: String,
+    tags: Vec<String>,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> Session {
+    Session {
+        id: 0,
+        project,
+        tags,
+        start,
+        end,
+        elapsed: end - start,
+        laps: Vec::new(),
+    }
+}
+
+// iCalendar
+
+fn to_ics(sessions: &[Session]) -> String {
+    let now = utc_timestamp(Local::now());
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//clock-cli//clock-cli//EN"),
+    ];
+    for session in sessions {
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!(
+            "UID:{}-{}@clock-cli",
+            session.id,
+            utc_timestamp(session.start)
+        ));
+        lines.push(format!("DTSTAMP:{}", now));
+        lines.push(format!("DTSTART:{}", utc_timestamp(session.start)));
+        lines.push(format!("DTEND:{}", utc_timestamp(session.end)));
+        lines.push(format!("SUMMARY:{}", escape_ics(&session.project)));
+        if !session.tags.is_empty() {
+            let tags: Vec<String> = session.tags.iter().map(|t| escape_ics(t)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        let mut description = format!("Tracked: {}", session.elapsed.hhmmss());
+        for (i, lap) in session.laps.iter().enumerate() {
+            description.push_str(&format!("\nLap {:02}: {}", i + 1, lap.time.hhmmssxxx()));
+            if let Some(label) = &lap.label {
+                description.push_str(&format!(" {}", label));
+            }
+        }
+        lines.push(format!("DESCRIPTION:{}", escape_ics(&description)));
+        lines.push(format!(
+            "X-CLOCK-ELAPSED:{}",
+            session.elapsed.num_milliseconds()
+        ));
+        for lap in &session.laps {
+            let mut line = format!("X-CLOCK-LAP:{}", lap.time.num_milliseconds());
+            if let Some(label) = &lap.label {
+                line.push_str(&format!(" {}", escape_ics(label)));
+            }
+            lines.push(line);
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines
+        .iter()
+        .map(|line| fold_ics_line(line))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+fn from_ics(content: &str) -> Result<Vec<Session>, BoxedError> {
+    // unfold lines which were split by `fold_ics_line`
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    let mut sessions = Vec::new();
+    let mut event: Option<IcsEvent> = None;
+    for line in lines {
+        let (name, value) = match line.find(':') {
+            Some(i) => (&line[..i], &line[i + 1..]),
+            None => continue,
+        };
+        let (name, params) = match name.find(';') {
+            Some(i) => (&name[..i], &name[i + 1..]),
+            None => (name, ""),
+        };
+        match (name.to_ascii_uppercase().as_str(), event.as_mut()) {
+            ("BEGIN", _) if value == "VEVENT" => event = Some(IcsEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => {
+                let e = event.take().unwrap();
+                let (start, end) = match (e.start, e.end) {
+                    (Some(start), Some(end)) => (start, end),
+                    _ => return Err("event without DTSTART or DTEND".into()),
+                };
+                let mut session = new_session(e.summary, e.categories, start, end);
+                if let Some(elapsed) = e.elapsed {
+                    session.elapsed = elapsed;
+                }
+                session.laps = e.laps;
+                sessions.push(session);
+            }
+            ("DTSTART", Some(e)) => e.start = Some(parse_ics_time(value, params)?),
+            ("DTEND", Some(e)) => e.end = Some(parse_ics_time(value, params)?),
+            ("SUMMARY", Some(e)) => e.summary = unescape_ics(value),
+            ("CATEGORIES", Some(e)) => {
+                e.categories
+                    .extend(split_ics_list(value).iter().map(|t| unescape_ics(t)));
+            }
+            ("X-CLOCK-ELAPSED", Some(e)) => {
+                e.elapsed = Some(Duration::milliseconds(value.trim().parse()?));
+            }
+            ("X-CLOCK-LAP", Some(e)) => {
+                let (ms, label) = match value.find(' ') {
+                    Some(i) => (&value[..i], unescape_ics(&value[i + 1..])),
+                    None => (value, String::new()),
+                };
+                e.laps.push(Lap {
+                    time: Duration::milliseconds(ms.parse()?),
+                    label: if label.is_empty() { None } else { Some(label) },
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(sessions)
+}
+
+#[derive(Default)]
+struct IcsEvent {
+    start: Option<DateTime<Local>>,
+    end: Option<DateTime<Local>>,
+    summary: String,
+    categories: Vec<String>,
+    elapsed: Option<Duration>,
+    laps: Vec<Lap>,
+}
+
+fn utc_timestamp(time: DateTime<Local>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Parses `20201012T080100Z` (UTC), or `20201012T090100` (local time). Times with a `TZID` are
+/// taken as local time.
+fn parse_ics_time(value: &str, _params: &str) -> Result<DateTime<Local>, BoxedError> {
+    let value = value.trim();
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+            Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+        }
+        None => {
+            let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+            Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .ok_or_else(|| format!("invalid local time: {}", value).into())
+        }
+    }
+}
+
+fn escape_ics(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape_ics(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Splits a list of values at the commas which are not escaped
+fn split_ics_list(s: &str) -> Vec<&str> {
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                values.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&s[start..]);
+    values
+}
+
+/// Folds a content line into lines of at most 75 bytes, ending with CRLF as RFC 5545 requires
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+// Org-mode
+
+fn to_org(sessions: &[Session]) -> String {
+    // one heading for each project and tags, in the order they first appear
+    let mut headings: Vec<(String, Vec<&Session>)> = Vec::new();
+    for session in sessions {
+        let mut heading = format!("* {}", session.project);
+        if !session.tags.is_empty() {
+            let tags: Vec<String> = session.tags.iter().map(|t| org_tag(t)).collect();
+            heading.push_str(&format!(" :{}:", tags.join(":")));
+        }
+        match headings.iter_mut().find(|(h, _)| *h == heading) {
+            Some((_, sessions)) => sessions.push(session),
+            None => headings.push((heading, vec![session])),
+        }
+    }
+    let mut org = String::new();
+    for (heading, sessions) in headings {
+        org.push_str(&heading);
+        org.push_str("\n  :LOGBOOK:\n");
+        for session in sessions {
+            let minutes = (session.end - session.start).num_minutes();
+            org.push_str(&format!(
+                "  CLOCK: [{}]--[{}] => {:>2}:{:02}\n",
+                session.start.format("%Y-%m-%d %a %H:%M"),
+                session.end.format("%Y-%m-%d %a %H:%M"),
+                minutes / 60,
+                minutes % 60
+            ));
+        }
+        org.push_str("  :END:\n");
+    }
+    org
+}
+
+fn from_org(content: &str) -> Result<Vec<Session>, BoxedError> {
+    let mut sessions = Vec::new();
+    let mut project = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if line.starts_with('*') {
+            let title = line.trim_start_matches('*').trim();
+            // trailing tags, e.g. `* project :tag1:tag2:`
+            match title.rfind(char::is_whitespace) {
+                Some(i) if title[i..].trim().starts_with(':') && title.ends_with(':') => {
+                    project = title[..i].trim().to_owned();
+                    tags = title[i..]
+                        .trim()
+                        .split(':')
+                        .filter(|t| !t.is_empty())
+                        .map(str::to_owned)
+                        .collect();
+                }
+                _ => {
+                    project = title.to_owned();
+                    tags = Vec::new();
+                }
+            }
+        } else if let Some(clock) = trimmed.strip_prefix("CLOCK:") {
+            let clock = clock.trim();
+            let (start, rest) = match clock.find("]--[") {
+                Some(i) => (&clock[..=i], &clock[i + 3..]),
+                None => continue, // still running
+            };
+            let end = match rest.find(']') {
+                Some(i) => &rest[..=i],
+                None => return Err(format!("invalid CLOCK line: {}", line).into()),
+            };
+            sessions.push(new_session(
+                project.clone(),
+                tags.clone(),
+                parse_org_time(start)?,
+                parse_org_time(end)?,
+            ));
+        }
+    }
+    Ok(sessions)
+}
+
+/// Parses `[2020-10-12 Mon 09:01]`
+fn parse_org_time(s: &str) -> Result<DateTime<Local>, BoxedError> {
+    let s = s.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut parts = s.split_whitespace();
+    let (date, time) = match (parts.next(), parts.nth(1)) {
+        (Some(date), Some(time)) => (date, time),
+        _ => return Err(format!("invalid Org timestamp: {}", s).into()),
+    };
+    let naive = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M")?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("invalid local time: {}", s).into())
+}
+
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_@#%".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// timewarrior
+
+fn to_timewarrior(sessions: &[Session]) -> String {
+    let mut data = String::new();
+    for session in sessions {
+        let tags: Vec<String> = std::iter::once(&session.project)
+            .chain(session.tags.iter())
+            .map(|t| timewarrior_tag(t))
+            .collect();
+        data.push_str(&format!(
+            "inc {} - {} # {}\n",
+            utc_timestamp(session.start),
+            utc_timestamp(session.end),
+            tags.join(" ")
+        ));
+    }
+    data
+}
+
+fn from_timewarrior(content: &str) -> Result<Vec<Session>, BoxedError> {
+    let mut sessions = Vec::new();
+    for line in content.lines() {
+        let line = match line.trim().strip_prefix("inc ") {
+            Some(line) => line,
+            None => continue,
+        };
+        let (interval, tags) = match line.find(" # ") {
+            Some(i) => (&line[..i], &line[i + 3..]),
+            None => (line, ""),
+        };
+        let mut times = interval.split(" - ");
+        let (start, end) = match (times.next(), times.next()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue, // still running
+        };
+        // annotations come after another `#`
+        let tags = match tags.find(" # ") {
+            Some(i) => &tags[..i],
+            None => tags,
+        };
+        let mut tags = split_timewarrior_tags(tags);
+        let project = if tags.is_empty() {
+            String::new()
+        } else {
+            tags.remove(0)
+        };
+        sessions.push(new_session(
+            project,
+            tags,
+            parse_ics_time(start, "")?,
+            parse_ics_time(end, "")?,
+        ));
+    }
+    Ok(sessions)
+}
+
+fn timewarrior_tag(tag: &str) -> String {
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == '"' || c == '#') {
+        format!("\"{}\"", tag.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        tag.to_owned()
+    }
+}
+
+fn split_timewarrior_tags(s: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut tag = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => tag.extend(chars.next()),
+                    '"' => break,
+                    c => tag.push(c),
+                }
+            }
+            tags.push(tag);
+        } else {
+            let mut tag = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                tag.push(c);
+                chars.next();
+            }
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A time on 12 October 2020, local time, on a whole minute so that Org-mode keeps it
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2020, 10, 12, hour, minute, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    fn sessions() -> Vec<Session> {
+        vec![
+            Session {
+                id: 1,
+                project: "clock-cli; docs, and more".to_owned(),
+                tags: vec!["writing".to_owned(), "long tag".to_owned()],
+                start: at(9, 1),
+                end: at(10, 31),
+                elapsed: Duration::milliseconds(4_500_123),
+                laps: vec![
+                    Lap {
+                        time: Duration::milliseconds(1_000_123),
+                        label: Some("intro, first draft".to_owned()),
+                    },
+                    Lap {
+                        time: Duration::milliseconds(3_500_000),
+                        label: None,
+                    },
+                ],
+            },
+            Session {
+                id: 2,
+                project: "reading".to_owned(),
+                tags: Vec::new(),
+                start: at(14, 0),
+                end: at(14, 45),
+                elapsed: Duration::minutes(45),
+                laps: Vec::new(),
+            },
+        ]
+    }
+
+    fn assert_same_times(imported: &[Session], sessions: &[Session]) {
+        assert_eq!(imported.len(), sessions.len());
+        for (imported, session) in imported.iter().zip(sessions.iter()) {
+            assert_eq!(imported.id, 0);
+            assert_eq!(imported.start, session.start);
+            assert_eq!(imported.end, session.end);
+        }
+    }
+
+    #[test]
+    fn ics_round_trip_is_lossless() {
+        let sessions = sessions();
+        let ics = export(&sessions, Format::ICalendar);
+        assert!(ics.lines().all(|line| line.len() <= 76)); // 75 bytes and CR
+        let imported = import(&ics, Format::ICalendar).unwrap();
+        assert_same_times(&imported, &sessions);
+        for (imported, session) in imported.iter().zip(sessions.iter()) {
+            assert_eq!(imported.project, session.project);
+            assert_eq!(imported.tags, session.tags);
+            assert_eq!(imported.elapsed, session.elapsed);
+            assert_eq!(imported.laps.len(), session.laps.len());
+            for (a, b) in imported.laps.iter().zip(session.laps.iter()) {
+                assert_eq!(a.time, b.time);
+                assert_eq!(a.label, b.label);
+            }
+        }
+    }
+
+    #[test]
+    fn org_round_trip() {
+        let sessions = sessions();
+        let org = export(&sessions, Format::Org);
+        assert!(org.contains("* reading\n  :LOGBOOK:\n  CLOCK: [2020-10-12 Mon 14:00]--[2020-10-12 Mon 14:45] =>  0:45\n  :END:\n"));
+        let imported = import(&org, Format::Org).unwrap();
+        assert_same_times(&imported, &sessions);
+        assert_eq!(imported[0].project, sessions[0].project);
+        assert_eq!(imported[0].tags, vec!["writing", "long_tag"]);
+        // the tracked time is the time between the start and the end, and the laps are lost
+        assert_eq!(imported[0].elapsed, Duration::minutes(90));
+        assert!(imported[0].laps.is_empty());
+        assert!(imported[1].tags.is_empty());
+    }
+
+    #[test]
+    fn timewarrior_round_trip() {
+        let sessions = sessions();
+        let data = export(&sessions, Format::Timewarrior);
+        assert!(data.ends_with(" # reading\n"));
+        let imported = import(&data, Format::Timewarrior).unwrap();
+        assert_same_times(&imported, &sessions);
+        for (imported, session) in imported.iter().zip(sessions.iter()) {
+            assert_eq!(imported.project, session.project);
+            assert_eq!(imported.tags, session.tags);
+        }
+    }
+
+    #[test]
+    fn timewarrior_open_intervals_and_annotations() {
+        let data = "inc 20201012T080100Z - 20201012T083100Z # clock \"a \\\"b\\\"\" # note\n\
+                    inc 20201012T090000Z # still running\n";
+        let imported = import(data, Format::Timewarrior).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].project, "clock");
+        assert_eq!(imported[0].tags, vec!["a \"b\""]);
+        assert_eq!(imported[0].elapsed, Duration::minutes(30));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(Format::from_path("sessions.ICS"), Some(Format::ICalendar));
+        assert_eq!(Format::from_path("notes.org"), Some(Format::Org));
+        assert_eq!(Format::from_path("2020-10.data"), Some(Format::Timewarrior));
+        assert_eq!(Format::from_path("sessions.csv"), None);
+        assert_eq!("timew".parse::<Format>(), Ok(Format::Timewarrior));
+        assert!("csv".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Game Clock
+//!
+//! A clock for turn-based games such as chess: each player has a countdown which only runs on
+//! their turn. The time control decides how much time a move gives back:
+//!
+//! - Fischer increment: a fixed time is added after each move.
+//! - Bronstein delay: the time a move took is added back after it, up to the delay.
+//! - Simple delay: the countdown only starts once the delay has passed on each move.
+//!
+//! A player whose countdown reaches zero loses on time ("the flag falls") and is skipped from then
+//! on, and the game is over when only one player is left. The time of every move is recorded, and
+//! can be exported to CSV or JSON.
+
+use crate::utils::{duration_ms, BoxedError};
+use chrono::{DateTime, Duration, Local};
+use hhmmss::Hhmmss;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimeControl {
+    /// No time is given back
+    SuddenDeath,
+    Fischer(Duration),
+    Bronstein(Duration),
+    Delay(Duration),
+}
+
+impl TimeControl {
+    /// The countdown after a move which took `used`, from `remaining` before it
+    fn after_move(self, remaining: Duration, used: Duration) -> Duration {
+        match self {
+            TimeControl::SuddenDeath => remaining - used,
+            TimeControl::Fischer(increment) => remaining - used + increment,
+            TimeControl::Bronstein(delay) => remaining - used + used.min(delay),
+            TimeControl::Delay(delay) => remaining - (used - delay).max(Duration::zero()),
+        }
+    }
+
+    /// The countdown while a move has taken `used` so far. The Fischer increment and the Bronstein
+    /// delay are only given once the move is made, so they cannot save a falling flag.
+    fn during_move(self, remaining: Duration, used: Duration) -> Duration {
+        match self {
+            TimeControl::Delay(delay) => remaining - (used - delay).max(Duration::zero()),
+            _ => remaining - used,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format = |d: &Duration| humantime::format_duration(d.to_std().unwrap_or_default());
+        match self {
+            TimeControl::SuddenDeath => write!(f, "sudden death"),
+            TimeControl::Fischer(d) => write!(f, "Fischer increment of {}", format(d)),
+            TimeControl::Bronstein(d) => write!(f, "Bronstein delay of {}", format(d)),
+            TimeControl::Delay(d) => write!(f, "simple delay of {}", format(d)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Player {
+    pub name: String,
+    /// The countdown at the start of the player's current (or next) move
+    #[serde(with = "duration_ms")]
+    pub remaining: Duration,
+    /// Whether the player has lost on time
+    pub flagged: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Move {
+    /// The index of the player who made the move
+    pub player: usize,
+    /// How long the move took
+    #[serde(with = "duration_ms")]
+    pub time: Duration,
+    /// The player's countdown after the move, including the time given back
+    #[serde(with = "duration_ms")]
+    pub remaining: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub players: Vec<Player>,
+    pub control: TimeControl,
+    pub moves: Vec<Move>,
+    active: usize,
+    /// When the active player's countdown was last started, or `None` when it is stopped
+    since: Option<DateTime<Local>>,
+    /// The time the current move took before the countdown was last stopped
+    used: Duration,
+}
+
+impl Game {
+    /// A game in which every player starts with `time`, and the first player moves first. The
+    /// countdown starts with `pause_or_resume()` or `switch()`.
+    pub fn new(names: Vec<String>, time: Duration, control: TimeControl) -> Self {
+        let players = names
+            .into_iter()
+            .map(|name| Player {
+                name,
+                remaining: time,
+                flagged: false,
+            })
+            .collect();
+        Self {
+            players,
+            control,
+            moves: Vec::new(),
+            active: 0,
+            since: None,
+            used: Duration::zero(),
+        }
+    }
+
+    /// The index of the player whose turn it is
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.since.is_some()
+    }
+
+    /// Whether fewer than two players have time left
+    pub fn is_over(&self) -> bool {
+        self.players.iter().filter(|p| !p.flagged).count() < 2
+    }
+
+    /// The last player with time left, once the game is over
+    pub fn winner(&self) -> Option<&Player> {
+        if self.is_over() {
+            self.players.iter().find(|p| !p.flagged)
+        } else {
+            None
+        }
+    }
+
+    /// The time the current move has taken so far
+    pub fn move_time(&self, now: DateTime<Local>) -> Duration {
+        self.used + self.since.map_or(Duration::zero(), |since| now - since)
+    }
+
+    /// The countdown of a player, as it is at `now`
+    pub fn remaining(&self, player: usize, now: DateTime<Local>) -> Duration {
+        let remaining = self.players[player].remaining;
+        if player == self.active && !self.is_over() {
+            let used = self.move_time(now);
+            self.control
+                .during_move(remaining, used)
+                .max(Duration::zero())
+        } else {
+            remaining
+        }
+    }
+
+    /// What is left of the simple delay of the current move, before the countdown starts
+    pub fn delay_left(&self, now: DateTime<Local>) -> Option<Duration> {
+        match self.control {
+            TimeControl::Delay(delay) if !self.is_over() => {
+                Some((delay - self.move_time(now)).max(Duration::zero()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Stops or starts the countdown of the active player
+    pub fn pause_or_resume(&mut self, now: DateTime<Local>) {
+        if self.is_over() {
+            return;
+        }
+        match self.since.take() {
+            Some(since) => self.used += now - since,
+            None => self.since = Some(now),
+        }
+    }
+
+    /// Ends the active player's move and starts the countdown of the next player with time left.
+    /// If the countdown is stopped (e.g. before the first move), starts it instead.
+    pub fn switch(&mut self, now: DateTime<Local>) {
+        if self.is_over() {
+            return;
+        }
+        if self.since.is_none() {
+            self.since = Some(now);
+            return;
+        }
+        // too late: the flag fell before the move was made
+        if self.check_flag(now).is_some() {
+            return;
+        }
+        let used = self.move_time(now);
+        let player = &mut self.players[self.active];
+        player.remaining = self.control.after_move(player.remaining, used);
+        self.moves.push(Move {
+            player: self.active,
+            time: used,
+            remaining: player.remaining,
+        });
+        self.next_turn(now);
+    }
+
+    /// Flags the active player if their countdown has reached zero, and returns their index
+    pub fn check_flag(&mut self, now: DateTime<Local>) -> Option<usize> {
+        if self.is_over() || self.remaining(self.active, now) > Duration::zero() {
+            return None;
+        }
+        let flagged = self.active;
+        let player = &mut self.players[flagged];
+        player.flagged = true;
+        player.remaining = Duration::zero();
+        if self.is_over() {
+            self.since = None;
+            self.used = Duration::zero();
+        } else {
+            self.next_turn(now);
+        }
+        Some(flagged)
+    }
+
+    fn next_turn(&mut self, now: DateTime<Local>) {
+        let n = self.players.len();
+        self.active = (1..=n)
+            .map(|i| (self.active + i) % n)
+            .find(|&i| !self.players[i].flagged)
+            .unwrap_or(self.active);
+        self.since = Some(now);
+        self.used = Duration::zero();
+    }
+
+    /// The moves of a player
+    pub fn moves_of(&self, player: usize) -> impl Iterator<Item = &Move> {
+        self.moves.iter().filter(move |m| m.player == player)
+    }
+
+    /// One line per move: the number of the move, the player, the time it took and the player's
+    /// countdown after it
+    pub fn to_csv(&self) -> String {
+        let mut csv = "move,player,time,remaining\n".to_owned();
+        for (i, m) in self.moves.iter().enumerate() {
+            let name = self.players[m.player].name.replace('"', "\"\"");
+            csv.push_str(&format!(
+                "{},\"{}\",{},{}\n",
+                i + 1,
+                name,
+                m.time.hhmmssxxx(),
+                m.remaining.hhmmssxxx()
+            ));
+        }
+        csv
+    }
+
+    /// The time control, the players and the moves, with the times in milliseconds
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            control: String,
+            players: &'a [Player],
+            moves: &'a [Move],
+        }
+        let export = Export {
+            control: self.control.to_string(),
+            players: &self.players,
+            moves: &self.moves,
+        };
+        serde_json::to_string_pretty(&export).unwrap()
+    }
+
+    /// Writes the moves to a `.json` file, or to a CSV file for any other extension
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<(), BoxedError> {
+        let path = path.as_ref();
+        let json = path
+            .extension()
+            .map_or(false, |ext| ext.eq_ignore_ascii_case("json"));
+        let content = if json { self.to_json() } else { self.to_csv() };
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(secs: i64) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2020, 10, 12, 20, 0, 0)
+            .earliest()
+            .unwrap()
+            + Duration::seconds(secs)
+    }
+
+    fn game(players: &[&str], time: i64, control: TimeControl) -> Game {
+        let names = players.iter().map(|&name| name.to_owned()).collect();
+        Game::new(names, Duration::seconds(time), control)
+    }
+
+    #[test]
+    fn time_controls() {
+        let s = Duration::seconds;
+        assert_eq!(TimeControl::SuddenDeath.after_move(s(60), s(10)), s(50));
+        assert_eq!(TimeControl::Fischer(s(5)).after_move(s(60), s(10)), s(55));
+        assert_eq!(TimeControl::Bronstein(s(5)).after_move(s(60), s(10)), s(55));
+        assert_eq!(TimeControl::Bronstein(s(5)).after_move(s(60), s(3)), s(60));
+        assert_eq!(TimeControl::Delay(s(5)).after_move(s(60), s(10)), s(55));
+        assert_eq!(TimeControl::Delay(s(5)).after_move(s(60), s(3)), s(60));
+
+        assert_eq!(TimeControl::Fischer(s(5)).during_move(s(60), s(10)), s(50));
+        assert_eq!(TimeControl::Bronstein(s(5)).during_move(s(60), s(3)), s(57));
+        assert_eq!(TimeControl::Delay(s(5)).during_move(s(60), s(3)), s(60));
+        assert_eq!(TimeControl::Delay(s(5)).during_move(s(60), s(10)), s(55));
+    }
+
+    #[test]
+    fn moves() {
+        let mut game = game(
+            &["White", "Black"],
+            60,
+            TimeControl::Fischer(Duration::seconds(2)),
+        );
+        game.switch(at(0));
+        assert!(game.is_running());
+        assert_eq!(game.active(), 0);
+        game.switch(at(10));
+        assert_eq!(game.active(), 1);
+        assert_eq!(game.remaining(0, at(20)), Duration::seconds(52));
+        assert_eq!(game.remaining(1, at(20)), Duration::seconds(50));
+        // paused from 20s to 40s
+        game.pause_or_resume(at(20));
+        assert_eq!(game.remaining(1, at(40)), Duration::seconds(50));
+        game.pause_or_resume(at(40));
+        game.switch(at(45));
+        assert_eq!(game.moves.len(), 2);
+        assert_eq!(game.moves[1].player, 1);
+        assert_eq!(game.moves[1].time, Duration::seconds(15));
+        assert_eq!(game.moves[1].remaining, Duration::seconds(47));
+        assert_eq!(
+            game.to_csv(),
+            "move,player,time,remaining\n\
+             1,\"White\",00:00:10.000,00:00:52.000\n\
+             2,\"Black\",00:00:15.000,00:00:47.000\n"
+        );
+    }
+
+    #[test]
+    fn flag() {
+        let mut game = game(&["A", "B", "C"], 30, TimeControl::SuddenDeath);
+        game.switch(at(0));
+        assert_eq!(game.check_flag(at(29)), None);
+        assert_eq!(game.check_flag(at(30)), Some(0));
+        assert!(game.players[0].flagged);
+        assert_eq!(game.active(), 1);
+        assert!(!game.is_over());
+        // a move made after the flag fell does not count
+        game.switch(at(70));
+        assert_eq!(game.check_flag(at(70)), None);
+        assert!(game.players[1].flagged);
+        assert!(game.moves.is_empty());
+        assert!(game.is_over());
+        assert_eq!(game.winner().map(|p| p.name.as_str()), Some("C"));
+        assert!(!game.is_running());
+    }
+
+    #[test]
+    fn delay_saves_the_flag() {
+        let mut game = game(&["A", "B"], 10, TimeControl::Delay(Duration::seconds(5)));
+        game.switch(at(0));
+        assert_eq!(game.delay_left(at(3)), Some(Duration::seconds(2)));
+        assert_eq!(game.check_flag(at(14)), None);
+        assert_eq!(game.remaining(0, at(14)), Duration::seconds(1));
+        assert_eq!(game.check_flag(at(15)), Some(0));
+        assert_eq!(game.winner().map(|p| p.name.as_str()), Some("B"));
+    }
+}
@@ -38,13 +38,23 @@
 //!
 //! simply run:
 //!
-//! ```
+//! ```text
 //! clock
 //! ```
 //!
 //! - Press `Space` to pause/resume.
-//! - Press `l` to lap.
+//! - Press `l` to lap. Mouse-scroll or press arrow keys to view more.
+//! - Press `m` to switch the column next to each lap between the difference from the previous lap, the difference from the average lap, and the cumulative split. The fastest lap is shown in green and the slowest in red.
+//! - Press `L` to lap and give the new lap a label (e.g. "compile"). Select a lap with the up/down arrow keys or a mouse click and press `n` to label it. Labels are shown next to the laps and in the summary.
+//! - Fix mistakes in the lap list: press `x` (or `Delete`) to delete the selected lap (its time goes to the next lap), `j` to merge the selected lap with the previous one, and `s` to split the selected (or the latest) lap in two. Press `u` to undo the last lap, edit, pause or resume.
 //! - Press `return` to finish.
+//! - Or click the `<Start>`/`<Pause>`, `<Lap>` and `<Stop>` buttons below the laps (`<Stop>` once the stopwatch has started).
+//!
+//! Set a target lap time to see how far the current lap is ahead of or behind it (it turns yellow when approaching the target and red when over it). Add `--notify` to get a notification whenever a lap goes over the target. The summary counts the laps over and under the target.
+//!
+//! ```text
+//! clock --target-lap 1m30s --notify
+//! ```
 //!
 //! ## Countdown Timer:
 //!
@@ -52,14 +62,253 @@
 //!
 //! Examples:
 //!
-//! ```
+//! ```text
 //! clock 3 minutes
 //! clock 4h3m
 //! clock 1 day
 //! ```
 //!
+//! The duration can also be a time to count down to, e.g. `clock until 15:00`, or have decimals, e.g. `clock 2.5 min`. On the config screen, press `e` to type a duration in any of these forms: the parsed duration and when the timer would end are shown as you type, `return` starts the timer and `Esc` goes back to the HH:MM:SS fields.
+//!
+//! Add `--progress` to show a progress bar as wide as the terminal, the percentage done, the elapsed and total time, and when the timer will end. The timer turns yellow when one of the `--color-at` thresholds is reached and red when two are (by default the last 10% and the last minute):
+//!
+//! ```text
+//! clock 25m --progress --color-at 20% --color-at 2m
+//! ```
+//!
+//! Add `--ring` to show a ring below the remaining time, like an analog countdown: it starts at 12 o'clock and shrinks back towards it as the time runs out (in the same colors as the timer):
+//!
+//! ```text
+//! clock 10m --ring
+//! ```
+//!
+//! Add `--warn-at` to be warned before the timer finishes, with the terminal bell, a notification and a flash of the remaining time. Thresholds are the time left or a percentage of the duration, and each warns once (again if time is added back above it):
+//!
+//! ```text
+//! clock 20m --warn-at 5m --warn-at 1m
+//! ```
+//!
+//! When the timer finishes, press `z` to snooze it (5 minutes by default, or `--snooze`), `r` to run the same duration again, or `return` to go back to the config screen. `--repeat` starts the timer again as soon as it finishes, N times or `forever`. Every run is recorded, and can be exported with `clock export --timers`:
+//!
+//! ```text
+//! clock 25m --repeat 4 --snooze 2m
+//! ```
+//!
+//! Durations of a day or more are shown with a days component, e.g. `6d 06:03:00.000`, and can be up to 999 days long.
+//!
 //! - Press `Space` to pause/resume.
+//! - Press `+`/`-` to add/subtract a minute.
 //! - Press `return` to cancel.
+//! - On the config screen, type digits into the focused field, or press `Up`/`Down` (or `k`/`j`) to change it by one and `PageUp`/`PageDown` by ten, carrying over to the next field and wrapping around at zero and at the longest duration. `Left`/`Right`, `Tab`/`Shift+Tab` and `Home`/`End` move between the fields, and `Backspace` deletes a digit.
+//! - With a mouse, click a field to focus it and scroll to change it (carrying over to the next field, e.g. 00:59 + 1 minute is 01:00), and click the `<Start>`, `<Pause>`, `<Cancel>`, `<Snooze>` and `<Repeat>` buttons.
+//!
+//! ## Metronome:
+//!
+//! Flash at a steady tempo, in beats per minute or at an interval, optionally with the terminal bell (`--bell`) or a sound file (`--sound`, played with `afplay` on macOS and `paplay` elsewhere). `--pattern` sets the accented (`X`), normal (`x` or `.`) and silent (`-`) beats of a bar, and `--accent-sound` plays another sound on the accented beats:
+//!
+//! ```text
+//! clock metronome 30s --bell          # e.g. during a manual load test
+//! clock metronome 96bpm --pattern X... --sound click.wav --accent-sound accent.wav
+//! ```
+//!
+//! - Press `Space` to start/stop (`--stopped` waits for it to start).
+//! - Press `Up`/`Down` (or `+`/`-`) to change the tempo by one beat per minute (or one second for an interval), and `PageUp`/`PageDown` by ten.
+//! - Press `t` a few times on the beat to set the tempo to the average time between the taps.
+//!
+//! The beats are scheduled against the monotonic clock, so the metronome does not drift however long it runs.
+//!
+//! ## Countdown to Events:
+//!
+//! Count down to release dates, holidays and anything else days or months away. Saved events are shown with the time left in days, hours, minutes and seconds, and a notification is sent when one is reached while the countdown is open:
+//!
+//! ```text
+//! clock countdown add "New Year" 2021-01-01
+//! clock countdown add "Launch" "2021-02-12 18:00"
+//! clock countdown add "Vacation" "40 days"
+//! clock countdown             # shows all saved events
+//! clock countdown --list      # prints them instead
+//! clock countdown remove Launch
+//! clock countdown 2021-01-01  # counts down without saving
+//! ```
+//!
+//! - Press `Up`/`Down` to select an event.
+//! - Press `x` or `Delete` to remove the selected event.
+//!
+//! Events are kept in `events.toml` in the data directory.
+//!
+//! ## Alarms:
+//!
+//! Recurring alarms ring with a notification at the times in their schedules, as long as `clock daemon` is running (e.g. started with your desktop session):
+//!
+//! ```text
+//! clock alarm add standup "weekdays 09:55"
+//! clock alarm add stretch "every 2h from 09:00 to 17:00"
+//! clock alarm add gym "mon,wed,fri 18:30"
+//! clock alarm                 # manages the alarms in a TUI
+//! clock alarm --list          # prints them with when each rings next
+//! clock alarm disable gym     # or enable
+//! clock alarm remove gym
+//! clock daemon
+//! ```
+//!
+//! A schedule is a time of day, optionally after the days it rings on (`daily`, `weekdays`, `weekends`, or days and ranges like `mon,wed,fri` and `mon-thu`), or `every` followed by an interval, optionally `from` and `to` a time of day.
+//!
+//! - Press `Up`/`Down` to select an alarm.
+//! - Press `Space` to enable/disable the selected alarm.
+//! - Press `z` to snooze it: an alarm which has just rung rings again in 5 minutes (or `--snooze`), and otherwise rings 5 minutes later than it would.
+//! - Press `x` or `Delete` to remove it.
+//!
+//! Alarms are kept in `config.toml` in the config directory (e.g. `~/.config/clock-cli` on Linux), which can be changed with the `CLOCK_CONFIG_DIR` environment variable. The daemon picks up changes to it right away.
+//!
+//! ## Wall Clock:
+//!
+//! Show the current time in large digits, filling the screen (e.g. on a spare monitor), with the date below it. `--analog` adds an analog face with hour, minute and second hands, drawn with braille dots, and `--zone` shows the time in other zones below the date, like the world clock:
+//!
+//! ```text
+//! clock now
+//! clock now --12 --no-seconds --date-format "%Y-%m-%d" --analog
+//! clock now --zone America/New_York --zone Asia/Tokyo
+//! ```
+//!
+//! - Press `t` to switch between 12 and 24 hours, `s` to show/hide the seconds and `a` to show/hide the analog face.
+//! - Press `q` or `Esc` to quit.
+//!
+//! ## Standup Speaker Timer:
+//!
+//! Give each speaker of a meeting a time box (2 minutes by default, or `--each`), and run a countdown timer for each of them in turn. A speaker can have their own time box, e.g. `Bob=3m`, and `--shuffle` shuffles the order:
+//!
+//! ```text
+//! clock standup Alice Bob=3m Carol --each 90s --shuffle
+//! ```
+//!
+//! - Press `n` or `return` to go to the next speaker.
+//! - Press `Space` to pause/resume.
+//!
+//! Once a time box is used up, the time spoken over it is shown in red. After the last speaker (or `<Quit>`), how long each speaker spoke is printed, with their time box and the difference.
+//!
+//! ## Game Clock:
+//!
+//! A chess clock for two or more players: each player has a countdown which only runs on their turn. Add a Fischer increment (`--increment`), a Bronstein delay (`--bronstein`) or a simple delay (`--delay`, the countdown only starts after it on each move):
+//!
+//! ```text
+//! clock game 5m --increment 3s
+//! clock game 10m --players Ann,Bob,Cy --delay 5s --export moves.csv
+//! ```
+//!
+//! - Press `Space` to start the clock, and then to end the move and start the next player's countdown.
+//! - Press `p` to pause/resume.
+//! - Press `return` to end the game and see the number of moves and the average time per move of each player.
+//!
+//! A player whose countdown reaches zero loses on time and is skipped from then on. `--export` writes the time of every move and the countdown after it to a CSV file, or to a JSON file if the name ends with `.json`.
+//!
+//! ## World Clock:
+//!
+//! Show the local time and the time in other time zones, with the offset from UTC, day (☀) or night (☾), and `+1 day`/`-1 day` when the date differs from yours. Time zones are IANA names, and the time zone database is bundled, so it works offline:
+//!
+//! ```text
+//! clock world add America/New_York Europe/London Asia/Kolkata
+//! clock world                 # shows the zones in the config file
+//! clock world Asia/Tokyo      # or the given zones
+//! clock world --list          # prints the times instead
+//! clock world remove Europe/London
+//! ```
+//!
+//! The zones are kept in `config.toml` in the config directory.
+//!
+//! ## Meeting Planner:
+//!
+//! Find a meeting slot within everyone's working hours (9 to 17, Monday to Friday, by default). Each time zone gets a 24-hour strip of the local day with its working hours shaded, and the last row marks the slots which suit everyone. Your own time zone always comes first and counts too, whether you list it or not:
+//!
+//! ```text
+//! clock plan                  # uses the zones in the config file
+//! clock plan America/New_York Asia/Kolkata --hours 8-18 --duration 30m --date 2020-11-02
+//! ```
+//!
+//! - Press `Left`/`Right` (or `h`/`l`) to move the slot by an hour, and `PageUp`/`PageDown` (or `<`/`>`) by a day. Or click an hour.
+//! - Press `n`/`N` to jump to the next/previous slot which suits everyone.
+//! - Press `return` to print the slot in every time zone, or `y` to also copy it to the clipboard (through the terminal, with the OSC 52 escape sequence).
+//!
+//! ## Daemon:
+//!
+//! Add `--detach` to hand a timer (or, without a duration, a stopwatch) to `clock daemon`, which keeps it running after the terminal is closed and sends a notification when the timer finishes. The daemon is started in the background if it is not running:
+//!
+//! ```text
+//! clock 20m --detach
+//! clock --detach              # a stopwatch
+//! clock attach                # shows the detached timers, stopwatches and the next alarms
+//! clock attach --plain        # prints them every second
+//! ```
+//!
+//! - Press `Up`/`Down` to select a timer or stopwatch.
+//! - Press `Space` to pause/resume it.
+//! - Press `l` to lap the selected stopwatch.
+//! - Press `x`, `Delete` or `return` to stop it (cancelling a timer).
+//!
+//! The daemon listens on `daemon.sock` in the data directory, taking one JSON request per connection (e.g. `{"request":"status"}`), so other clients can show or control the timers too.
+//!
+//! ## Time Tracking:
+//!
+//! Track the time spent on a project, with optional tags. The stopwatch starts right away, and the session is saved when it stops:
+//!
+//! ```text
+//! clock track start billing +client-a +meeting
+//! ```
+//!
+//! Manage past sessions:
+//!
+//! ```text
+//! clock track list [--project billing] [--tag client-a]
+//! clock track edit 3 --project billing --tags +client-b --start "2020-10-10 09:00" --end "2020-10-10 11:30"
+//! clock track delete 3
+//! clock track continue [3]    # starts a new session with the project and tags of session 3 (or the latest)
+//! ```
+//!
+//! Report the tracked time per day, week, project or tag, as a table with a bar chart. `--tui` shows the report in a TUI where the grouping can be switched:
+//!
+//! ```text
+//! clock report --from 2020-10-01 --to 2020-10-31 --group-by project [--tui]
+//! ```
+//!
+//! Sessions are kept in `sessions.jsonl` in the data directory (e.g. `~/.local/share/clock-cli` on Linux), which can be changed with the `CLOCK_DATA_DIR` environment variable.
+//!
+//! Export sessions to iCalendar, Org-mode `CLOCK` entries or the timewarrior data format (by `--format`, or the extension of the output file), and import them back. `--timers` also exports the runs of the countdown timer:
+//!
+//! ```text
+//! clock export --from 2020-10-01 --to 2020-10-31 -o october.ics [--timers]
+//! clock export --format org
+//! clock import october.ics
+//! ```
+//!
+//! iCalendar keeps the tracked time and laps, so a round trip is lossless. Org-mode and timewarrior only keep the start and end of each session. Sessions already in the store (same project, start and end) are skipped when importing.
+//!
+//! ## Speedrun Split Timer:
+//!
+//! Run the segments listed in a LiveSplit `.lss` file or a TOML file, comparing against your personal best:
+//!
+//! ```text
+//! clock splits celeste.lss
+//! ```
+//!
+//! A TOML splits file looks like this (the times are optional):
+//!
+//! ```toml
+//! game = "Celeste"
+//! category = "Any%"
+//!
+//! [[segments]]
+//! name = "Forsaken City"
+//! personal_best = "00:05:12.345" # split time, i.e. from the start of the run
+//! best_segment = "00:05:01.000"
+//!
+//! [[segments]]
+//! name = "Old Site"
+//! ```
+//!
+//! - Press `Space` to start, and to pause/resume.
+//! - Press `l` to split.
+//! - Press `return` to reset.
+//!
+//! Finished segments show the difference from the personal best in green (ahead), red (behind) or yellow (best segment ever). New personal bests and best segments are saved back to the file. Convert between the formats with `clock splits celeste.lss --export celeste.toml`.
 //!
 //! # Compatibility
 //!
@@ -68,6 +317,18 @@
 //! # Acknowledgement
 //!
 //! The TUI is based on the [**cursive**](https://github.com/gyscos/cursive) crate made by [Alexandre Bury (@glycos)](https://github.com/gyscos), who also helped me a lot during the development of this crate (see [glycose/cursive/#503](https://github.com/gyscos/cursive/pull/503))
+pub mod alarm;
+pub mod config;
+pub mod countdown;
+pub mod daemon;
+pub mod export;
+pub mod game;
+pub mod metronome;
 pub(crate) mod notify;
+pub mod report;
+pub mod splits;
+pub mod standup;
+pub mod track;
 pub mod tui;
 pub mod utils;
+pub mod world;
@@ -15,25 +15,1145 @@
 // You should have received a copy of the GNU General Public License
 // along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
 
-use clock_cli::tui;
-use std::env;
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use chrono_tz::Tz;
+use clap::{crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use clock_cli::{
+    alarm::{Alarm, Schedule},
+    config::Config,
+    countdown::{parse_target, CountdownEvent, EventStore},
+    daemon::{self, Request, Response},
+    export::{self, Format},
+    game::{Game, TimeControl},
+    metronome::{Pattern, Tempo},
+    report::{in_range, GroupBy, Report},
+    splits::Splits,
+    standup::Speaker,
+    track::{Session, SessionStore, TimerRun},
+    tui::{self, MetronomeView, Repeat, Threshold, TimerView, WallClockView},
+    utils::{check_date_format, dhhmmss, parse_datetime},
+    world::{day_marker, parse_zone, utc_offset, WorkingHours},
+};
+use hhmmss::Hhmmss;
+use rand::seq::SliceRandom;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
 
 fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
-    if args.len() == 0 {
-        tui::stopwatch();
+    let matches = App::new("clock")
+        .version(crate_version!())
+        .about("Clock utilities (stopwatch, timer) on the command line")
+        .setting(AppSettings::ArgsNegateSubcommands)
+        .arg(
+            Arg::with_name("DURATION")
+                .help("Duration of the countdown timer, e.g. \"3 minutes\", 4h3m, \"2.5 min\" or \"until 15:00\". Runs a stopwatch if omitted.")
+                .multiple(true),
+        )
+        .arg(
+            Arg::with_name("detach")
+                .long("detach")
+                .help("Hands the timer (or stopwatch) to the daemon, which starts if it is not running, instead of showing it. See `clock attach`.")
+                .conflicts_with_all(&["progress", "ring", "color-at", "warn-at", "snooze", "repeat", "target-lap", "notify"]),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .help("Shows a progress bar, the percentage done and when the timer will end")
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("ring")
+                .long("ring")
+                .help("Shows a ring which shrinks as the time runs out, like an analog countdown")
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("color-at")
+                .long("color-at")
+                .value_name("THRESHOLD")
+                .help("The timer turns yellow when one threshold is reached, and red when two are, e.g. --color-at 10% --color-at 1m (the default)")
+                .multiple(true)
+                .number_of_values(1)
+                .validator(|s| s.parse::<Threshold>().map(|_| ()))
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("warn-at")
+                .long("warn-at")
+                .value_name("THRESHOLD")
+                .help("Sends a notification when this much time is left, e.g. --warn-at 5m --warn-at 1m, or --warn-at 10%")
+                .multiple(true)
+                .number_of_values(1)
+                .validator(|s| s.parse::<Threshold>().map(|_| ()))
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("snooze")
+                .long("snooze")
+                .value_name("DURATION")
+                .help("How long to snooze the finished timer for with \"z\" [default: 5m]")
+                .validator(|s| parse_duration(&s).map(|_| ()))
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("repeat")
+                .long("repeat")
+                .value_name("N|forever")
+                .help("Starts the timer again when it finishes, until it has run N times (or forever)")
+                .validator(|s| s.parse::<Repeat>().map(|_| ()))
+                .requires("DURATION"),
+        )
+        .arg(
+            Arg::with_name("target-lap")
+                .long("target-lap")
+                .value_name("DURATION")
+                .help("Target lap time of the stopwatch, e.g. 1m30s")
+                .validator(|s| parse_duration(&s).map(|_| ()))
+                .conflicts_with("DURATION"),
+        )
+        .arg(
+            Arg::with_name("notify")
+                .long("notify")
+                .help("Sends a notification whenever a lap goes over the target")
+                .requires("target-lap"),
+        )
+        .subcommand(
+            SubCommand::with_name("splits")
+                .about("Speedrun split timer")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Splits as a LiveSplit .lss file or a TOML file")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("export")
+                        .long("export")
+                        .value_name("OUTPUT")
+                        .help("Converts the splits to .lss or TOML (by the extension of OUTPUT) instead of running them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("track")
+                .about("Tracks time spent on projects")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("start")
+                        .about("Starts tracking time for a project")
+                        .arg(Arg::with_name("PROJECT").required(true))
+                        .arg(
+                            Arg::with_name("TAGS")
+                                .help("Tags of the session, e.g. +client-a +meeting")
+                                .multiple(true),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("continue")
+                        .about("Starts a new session with the project and tags of a past session")
+                        .arg(Arg::with_name("ID").help("Defaults to the latest session")),
+                )
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("Lists past sessions")
+                        .arg(
+                            Arg::with_name("project")
+                                .long("project")
+                                .value_name("PROJECT")
+                                .help("Only lists sessions of this project"),
+                        )
+                        .arg(
+                            Arg::with_name("tag")
+                                .long("tag")
+                                .value_name("TAG")
+                                .help("Only lists sessions with this tag"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("edit")
+                        .about("Edits a past session")
+                        .arg(Arg::with_name("ID").required(true))
+                        .arg(
+                            Arg::with_name("project")
+                                .long("project")
+                                .value_name("PROJECT"),
+                        )
+                        .arg(
+                            Arg::with_name("tags")
+                                .long("tags")
+                                .value_name("TAGS")
+                                .help("Replaces the tags, e.g. --tags +client-a +meeting")
+                                .min_values(0),
+                        )
+                        .arg(
+                            Arg::with_name("start")
+                                .long("start")
+                                .value_name("TIME")
+                                .help("e.g. \"2020-10-10 09:00\". The tracked time becomes the time between start and end.")
+                                .validator(|s| parse_datetime(&s).map(|_| ())),
+                        )
+                        .arg(
+                            Arg::with_name("end")
+                                .long("end")
+                                .value_name("TIME")
+                                .help("e.g. \"2020-10-10 17:30\". The tracked time becomes the time between start and end.")
+                                .validator(|s| parse_datetime(&s).map(|_| ())),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("delete")
+                        .about("Deletes a past session")
+                        .arg(Arg::with_name("ID").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Reports the time tracked with `clock track`")
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("DATE")
+                        .help("Only includes sessions started on or after this date, e.g. 2020-10-01")
+                        .validator(|s| parse_datetime(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("DATE")
+                        .help("Only includes sessions started on or before this date, e.g. 2020-10-31")
+                        .validator(|s| parse_datetime(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("group-by")
+                        .long("group-by")
+                        .value_name("GROUP")
+                        .possible_values(&["day", "week", "project", "tag"])
+                        .default_value("day"),
+                )
+                .arg(
+                    Arg::with_name("tui")
+                        .long("tui")
+                        .help("Shows the report in a TUI, where the grouping can be changed"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("countdown")
+                .about("Counts down to events days or months away, e.g. release dates and holidays")
+                .setting(AppSettings::ArgsNegateSubcommands)
+                .arg(
+                    Arg::with_name("WHEN")
+                        .help("Counts down to this date/time or duration without saving it, e.g. \"2021-01-01\" or \"40 days\". Lists all saved events if omitted.")
+                        .validator(|s| parse_target(&s, Local::now()).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help("Prints the saved events instead of showing them in a TUI")
+                        .conflicts_with("WHEN"),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Saves an event, replacing the one with the same name")
+                        .arg(Arg::with_name("NAME").required(true))
+                        .arg(
+                            Arg::with_name("WHEN")
+                                .help("e.g. \"2021-01-01\", \"2021-02-12 18:00\" or \"40 days\"")
+                                .required(true)
+                                .validator(|s| parse_target(&s, Local::now()).map(|_| ())),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Removes a saved event")
+                        .arg(Arg::with_name("NAME").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("alarm")
+                .about("Manages recurring alarms, which are rung by `clock daemon`")
+                .setting(AppSettings::ArgsNegateSubcommands)
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help("Prints the alarms instead of showing them in a TUI"),
+                )
+                .arg(
+                    Arg::with_name("snooze")
+                        .long("snooze")
+                        .value_name("DURATION")
+                        .help("How long to snooze an alarm for with \"z\" [default: 5m]")
+                        .validator(|s| parse_duration(&s).map(|_| ())),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Adds an alarm, replacing the one with the same name")
+                        .arg(Arg::with_name("NAME").required(true))
+                        .arg(
+                            Arg::with_name("SCHEDULE")
+                                .help("e.g. \"09:00\", \"weekdays 09:55\", \"mon,wed,fri 18:30\" or \"every 2h from 09:00\"")
+                                .required(true)
+                                .validator(|s| s.parse::<Schedule>().map(|_| ())),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Removes an alarm")
+                        .arg(Arg::with_name("NAME").required(true)),
+                )
+                .subcommand(
+                    SubCommand::with_name("enable")
+                        .about("Enables an alarm")
+                        .arg(Arg::with_name("NAME").required(true)),
+                )
+                .subcommand(
+                    SubCommand::with_name("disable")
+                        .about("Disables an alarm")
+                        .arg(Arg::with_name("NAME").required(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("game")
+                .about("Chess clock: a countdown per player, which only runs on their turn")
+                .arg(
+                    Arg::with_name("TIME")
+                        .help("Time per player, e.g. 5m")
+                        .required(true)
+                        .validator(|s| parse_duration(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("players")
+                        .long("players")
+                        .value_name("NAMES")
+                        .help("Names of the players, in the order of play")
+                        .use_delimiter(true)
+                        .min_values(2)
+                        .default_value("White,Black"),
+                )
+                .arg(
+                    Arg::with_name("increment")
+                        .long("increment")
+                        .value_name("DURATION")
+                        .help("Adds this much time after each move (Fischer)")
+                        .validator(|s| parse_duration(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("bronstein")
+                        .long("bronstein")
+                        .value_name("DURATION")
+                        .help("Adds back the time each move took, up to this much (Bronstein delay)")
+                        .validator(|s| parse_duration(&s).map(|_| ()))
+                        .conflicts_with("increment"),
+                )
+                .arg(
+                    Arg::with_name("delay")
+                        .long("delay")
+                        .value_name("DURATION")
+                        .help("Starts the countdown only after this much time on each move (simple delay)")
+                        .validator(|s| parse_duration(&s).map(|_| ()))
+                        .conflicts_with_all(&["increment", "bronstein"]),
+                )
+                .arg(
+                    Arg::with_name("export")
+                        .long("export")
+                        .value_name("OUTPUT")
+                        .help("Writes the time of every move to OUTPUT when the game ends, as JSON (.json) or CSV"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("standup")
+                .about("Runs a timer for each speaker in turn, e.g. in a standup meeting")
+                .arg(
+                    Arg::with_name("SPEAKERS")
+                        .help("Names of the speakers, optionally with their own time box, e.g. Alice Bob=3m")
+                        .required(true)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("each")
+                        .long("each")
+                        .value_name("DURATION")
+                        .help("Time box of each speaker")
+                        .default_value("2m")
+                        .validator(|s| parse_duration(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("shuffle")
+                        .long("shuffle")
+                        .help("Shuffles the order of the speakers"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("metronome")
+                .about("Flashes, rings the bell or plays a sound at a steady tempo")
+                .arg(
+                    Arg::with_name("TEMPO")
+                        .help("Beats per minute, e.g. 120bpm, or an interval, e.g. 30s")
+                        .default_value("60bpm")
+                        .validator(|s| s.parse::<Tempo>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("pattern")
+                        .long("pattern")
+                        .value_name("PATTERN")
+                        .help("Accented (X), normal (x or .) and silent (-) beats of a bar, e.g. X... or X.x.")
+                        .validator(|s| s.parse::<Pattern>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("bell")
+                        .long("bell")
+                        .help("Rings the terminal bell on every beat"),
+                )
+                .arg(
+                    Arg::with_name("sound")
+                        .long("sound")
+                        .value_name("FILE")
+                        .help("Plays this sound file on every beat (with afplay on macOS, paplay elsewhere)"),
+                )
+                .arg(
+                    Arg::with_name("accent-sound")
+                        .long("accent-sound")
+                        .value_name("FILE")
+                        .help("Plays this sound file on accented beats instead"),
+                )
+                .arg(
+                    Arg::with_name("stopped")
+                        .long("stopped")
+                        .help("Waits for Space to start, e.g. to tap the tempo first"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("now")
+                .about("Shows the current time in large digits on the whole screen")
+                .arg(
+                    Arg::with_name("12")
+                        .long("12")
+                        .help("Shows the time in 12-hour format"),
+                )
+                .arg(
+                    Arg::with_name("no-seconds")
+                        .long("no-seconds")
+                        .help("Hides the seconds"),
+                )
+                .arg(
+                    Arg::with_name("date-format")
+                        .long("date-format")
+                        .value_name("FORMAT")
+                        .validator(|s| check_date_format(&s))
+                        .help("Format of the date, e.g. \"%Y-%m-%d\" [default: \"%A, %-d %B %Y\"]"),
+                )
+                .arg(
+                    Arg::with_name("no-date")
+                        .long("no-date")
+                        .help("Hides the date")
+                        .conflicts_with("date-format"),
+                )
+                .arg(
+                    Arg::with_name("analog")
+                        .long("analog")
+                        .help("Shows an analog face above the digits"),
+                )
+                .arg(
+                    Arg::with_name("zone")
+                        .long("zone")
+                        .value_name("ZONE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(|s| parse_zone(&s).map(|_| ()))
+                        .help("Shows the time in this zone below the clock, e.g. --zone Asia/Tokyo --zone Europe/London"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("world")
+                .about("Shows the time in several time zones")
+                .setting(AppSettings::ArgsNegateSubcommands)
+                .arg(
+                    Arg::with_name("ZONES")
+                        .help("IANA time zones, e.g. America/New_York Asia/Tokyo. Shows the zones in the config file if omitted.")
+                        .multiple(true)
+                        .validator(|s| parse_zone(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help("Prints the times instead of showing them in a TUI"),
+                )
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Adds time zones to the config file")
+                        .arg(
+                            Arg::with_name("ZONES")
+                                .required(true)
+                                .multiple(true)
+                                .validator(|s| parse_zone(&s).map(|_| ())),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Removes time zones from the config file")
+                        .arg(Arg::with_name("ZONES").required(true).multiple(true)),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("plan")
+                .about("Finds a meeting slot within everyone's working hours across time zones")
+                .arg(
+                    Arg::with_name("ZONES")
+                        .help("IANA time zones, e.g. America/New_York Asia/Tokyo, besides the local one. Uses the zones in the config file if omitted.")
+                        .multiple(true)
+                        .validator(|s| parse_zone(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("hours")
+                        .long("hours")
+                        .value_name("START-END")
+                        .help("Working hours, Monday to Friday, in each zone")
+                        .default_value("9-17")
+                        .validator(|s| s.parse::<WorkingHours>().map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .long("duration")
+                        .value_name("DURATION")
+                        .help("Length of the meeting")
+                        .default_value("1h")
+                        .validator(|s| parse_duration(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .value_name("YYYY-MM-DD")
+                        .help("The (local) day to start on, today by default")
+                        .validator(|s| parse_datetime(&s).map(|_| ())),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("daemon")
+                .about("Runs in the background, owning the detached timers and stopwatches and ringing the alarms"),
+        )
+        .subcommand(
+            SubCommand::with_name("attach")
+                .about("Shows the live state of the timers, stopwatches and alarms owned by the daemon")
+                .arg(
+                    Arg::with_name("plain")
+                        .long("plain")
+                        .help("Prints the state every second instead of showing it in a TUI"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Exports tracked sessions to iCalendar, Org-mode or timewarrior")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["ics", "org", "timewarrior"])
+                        .help("Defaults to the extension of OUTPUT (.ics, .org or .data), or ics"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("OUTPUT")
+                        .help("Writes to this file instead of the standard output"),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("DATE")
+                        .help("Only includes sessions started on or after this date, e.g. 2020-10-01")
+                        .validator(|s| parse_datetime(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("DATE")
+                        .help("Only includes sessions started on or before this date, e.g. 2020-10-31")
+                        .validator(|s| parse_datetime(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("timers")
+                        .long("timers")
+                        .help("Also includes runs of the countdown timer"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Imports sessions from iCalendar, Org-mode or timewarrior")
+                .arg(Arg::with_name("FILE").required(true))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["ics", "org", "timewarrior"])
+                        .help("Defaults to the extension of FILE (.ics, .org or .data)"),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        ("splits", Some(matches)) => splits(matches),
+        ("track", Some(matches)) => track(matches),
+        ("report", Some(matches)) => report(matches),
+        ("countdown", Some(matches)) => countdown(matches),
+        ("alarm", Some(matches)) => alarm(matches),
+        ("world", Some(matches)) => world(matches),
+        ("now", Some(matches)) => now(matches),
+        ("game", Some(matches)) => game(matches),
+        ("standup", Some(matches)) => standup(matches),
+        ("metronome", Some(matches)) => metronome(matches),
+        ("plan", Some(matches)) => tui::plan(
+            zones(matches),
+            matches.value_of("hours").unwrap().parse().unwrap(),
+            parse_duration(matches.value_of("duration").unwrap()).unwrap(),
+            matches
+                .value_of("date")
+                .map(|s| parse_datetime(s).unwrap().date_naive()),
+        ),
+        ("daemon", Some(_)) => daemon::run().unwrap_or_else(|e| exit_with_error(e)),
+        ("export", Some(matches)) => export(matches),
+        ("import", Some(matches)) => import(matches),
+        ("attach", Some(matches)) => {
+            if matches.is_present("plain") {
+                attach_plain()
+            } else {
+                tui::attach()
+            }
+        }
+        _ if matches.is_present("detach") => detach(&matches),
+        _ => match matches.values_of("DURATION") {
+            None => tui::stopwatch(
+                matches
+                    .value_of("target-lap")
+                    .map(|s| parse_duration(s).unwrap()),
+                matches.is_present("notify"),
+            ),
+            Some(words) => {
+                let input = words.collect::<Vec<_>>().join(" ");
+                let duration = TimerView::parse_duration(&input, Local::now())
+                    .unwrap_or_else(|e| exit_with_error(e));
+                let warnings = matches
+                    .values_of("warn-at")
+                    .into_iter()
+                    .flatten()
+                    .map(|s| s.parse().unwrap())
+                    .collect();
+                let mut timer = TimerView::new(duration).with_warnings(warnings);
+                if matches.is_present("progress") {
+                    timer = timer.with_progress_bar();
+                }
+                if matches.is_present("ring") {
+                    timer = timer.with_ring();
+                }
+                if let Some(thresholds) = matches.values_of("color-at") {
+                    timer = timer
+                        .with_color_thresholds(thresholds.map(|s| s.parse().unwrap()).collect());
+                }
+                if let Some(snooze) = matches.value_of("snooze") {
+                    timer = timer.with_snooze(parse_duration(snooze).unwrap());
+                }
+                if let Some(repeat) = matches.value_of("repeat") {
+                    timer = timer.with_auto_repeat(repeat.parse().unwrap());
+                }
+                tui::timer(timer);
+            }
+        },
+    }
+}
+
+fn splits(matches: &ArgMatches) {
+    let path = PathBuf::from(matches.value_of("FILE").unwrap());
+    let splits = Splits::load(&path)
+        .unwrap_or_else(|e| exit_with_error(format!("failed to load {}: {}", path.display(), e)));
+    match matches.value_of("export") {
+        Some(output) => {
+            if let Err(e) = splits.save(output) {
+                exit_with_error(format!("failed to export to {}: {}", output, e));
+            }
+        }
+        None => tui::splits(splits, path),
+    }
+}
+
+fn track(matches: &ArgMatches) {
+    let mut store = SessionStore::open().unwrap_or_else(|e| exit_with_error(e));
+    match matches.subcommand() {
+        ("start", Some(matches)) => tui::track(
+            matches.value_of("PROJECT").unwrap().to_owned(),
+            parse_tags(matches.values_of("TAGS")),
+        ),
+        ("continue", Some(matches)) => {
+            let session = match matches.value_of("ID") {
+                Some(id) => store.get(parse_id(id)),
+                None => store.latest(),
+            }
+            .unwrap_or_else(|| exit_with_error("no such session"));
+            tui::track(session.project.clone(), session.tags.clone());
+        }
+        ("list", Some(matches)) => {
+            let project = matches.value_of("project");
+            let tag = matches.value_of("tag").map(|t| t.trim_start_matches('+'));
+            println!(
+                "{:>4}  {:10}  {:5}  {:5}  {:8}  Project",
+                "ID", "Date", "Start", "End", "Tracked"
+            );
+            for session in store.sessions().iter().filter(|s| {
+                project.map_or(true, |p| s.project == p)
+                    && tag.map_or(true, |t| s.tags.iter().any(|tag| tag == t))
+            }) {
+                println!("{}", format_session(session));
+            }
+        }
+        ("edit", Some(matches)) => {
+            let mut session = store
+                .get(parse_id(matches.value_of("ID").unwrap()))
+                .cloned()
+                .unwrap_or_else(|| exit_with_error("no such session"));
+            if let Some(project) = matches.value_of("project") {
+                session.project = project.to_owned();
+            }
+            if matches.is_present("tags") {
+                session.tags = parse_tags(matches.values_of("tags"));
+            }
+            let start = matches
+                .value_of("start")
+                .map(|s| parse_datetime(s).unwrap());
+            let end = matches.value_of("end").map(|s| parse_datetime(s).unwrap());
+            if start.is_some() || end.is_some() {
+                session.start = start.unwrap_or(session.start);
+                session.end = end.unwrap_or(session.end);
+                if session.end < session.start {
+                    exit_with_error("the session would end before it starts");
+                }
+                session.elapsed = session.end - session.start;
+            }
+            store
+                .edit(session.clone())
+                .unwrap_or_else(|e| exit_with_error(e));
+            println!("{}", format_session(&session));
+        }
+        ("delete", Some(matches)) => {
+            let id = parse_id(matches.value_of("ID").unwrap());
+            store.delete(id).unwrap_or_else(|e| exit_with_error(e));
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn report(matches: &ArgMatches) {
+    let store = SessionStore::open().unwrap_or_else(|e| exit_with_error(e));
+    let (from, to) = date_range(matches);
+    let group_by: GroupBy = matches.value_of("group-by").unwrap().parse().unwrap();
+    let sessions: Vec<Session> = store
+        .sessions()
+        .iter()
+        .filter(|s| in_range(s, from, to))
+        .cloned()
+        .collect();
+    if matches.is_present("tui") {
+        tui::report(sessions, group_by);
+    } else {
+        print!("{}", Report::new(&sessions, group_by).to_table(40));
+    }
+}
+
+fn countdown(matches: &ArgMatches) {
+    let mut store = EventStore::open().unwrap_or_else(|e| exit_with_error(e));
+    match matches.subcommand() {
+        ("add", Some(matches)) => {
+            let event = CountdownEvent {
+                name: matches.value_of("NAME").unwrap().to_owned(),
+                target: parse_target(matches.value_of("WHEN").unwrap(), Local::now()).unwrap(),
+            };
+            store.add(event).unwrap_or_else(|e| exit_with_error(e));
+        }
+        ("remove", Some(matches)) => {
+            let name = matches.value_of("NAME").unwrap();
+            store.remove(name).unwrap_or_else(|e| exit_with_error(e));
+        }
+        _ => match matches.value_of("WHEN") {
+            Some(when) => {
+                let event = CountdownEvent {
+                    name: when.to_owned(),
+                    target: parse_target(when, Local::now()).unwrap(),
+                };
+                tui::countdown(vec![event], false);
+            }
+            None if matches.is_present("list") => {
+                let now = Local::now();
+                for event in store.events() {
+                    let remaining = event.remaining(now);
+                    println!(
+                        "{}  {:>16}{}  {}",
+                        event.target.format("%Y-%m-%d %a %H:%M"),
+                        dhhmmss(remaining),
+                        if remaining > Duration::zero() {
+                            ""
+                        } else {
+                            " ago"
+                        },
+                        event.name
+                    );
+                }
+            }
+            None => tui::countdown(store.events().to_vec(), true),
+        },
+    }
+}
+
+fn alarm(matches: &ArgMatches) {
+    let mut config = Config::open().unwrap_or_else(|e| exit_with_error(e));
+    match matches.subcommand() {
+        ("add", Some(matches)) => {
+            let alarm = Alarm::new(
+                matches.value_of("NAME").unwrap().to_owned(),
+                matches.value_of("SCHEDULE").unwrap().parse().unwrap(),
+            );
+            config
+                .add_alarm(alarm)
+                .unwrap_or_else(|e| exit_with_error(e));
+        }
+        ("remove", Some(matches)) => {
+            let name = matches.value_of("NAME").unwrap();
+            config
+                .remove_alarm(name)
+                .unwrap_or_else(|e| exit_with_error(e));
+        }
+        (enable @ "enable", Some(matches)) | (enable @ "disable", Some(matches)) => {
+            let name = matches.value_of("NAME").unwrap();
+            let mut alarm = config
+                .alarm(name)
+                .cloned()
+                .unwrap_or_else(|| exit_with_error(format!("no alarm named {}", name)));
+            alarm.enabled = enable == "enable";
+            alarm.snoozed_until = None;
+            config
+                .add_alarm(alarm)
+                .unwrap_or_else(|e| exit_with_error(e));
+        }
+        _ if matches.is_present("list") => {
+            let now = Local::now();
+            let width = config
+                .alarms
+                .iter()
+                .map(|a| a.schedule.to_string().len())
+                .max()
+                .unwrap_or(0);
+            for alarm in &config.alarms {
+                let next = match alarm.next_ring(now) {
+                    Some(ring) => ring.format("%Y-%m-%d %a %H:%M").to_string(),
+                    None if alarm.enabled => "never".to_owned(),
+                    None => "disabled".to_owned(),
+                };
+                println!(
+                    "{:<20}  {:<width$}  {}",
+                    next,
+                    alarm.schedule.to_string(),
+                    alarm.name,
+                    width = width
+                );
+            }
+        }
+        _ => {
+            let snooze = matches
+                .value_of("snooze")
+                .map_or(Duration::minutes(5), |s| parse_duration(s).unwrap());
+            tui::alarms(config.alarms, snooze);
+        }
+    }
+}
+
+fn now(matches: &ArgMatches) {
+    let mut view = WallClockView::new();
+    if matches.is_present("12") {
+        view = view.with_12_hour();
+    }
+    if matches.is_present("no-seconds") {
+        view = view.without_seconds();
+    }
+    if matches.is_present("no-date") {
+        view = view.with_date_format(None);
+    }
+    if let Some(format) = matches.value_of("date-format") {
+        view = view.with_date_format(Some(format.to_owned()));
+    }
+    if matches.is_present("analog") {
+        view = view.with_analog_face();
+    }
+    let zones = matches
+        .values_of("zone")
+        .into_iter()
+        .flatten()
+        .map(|z| parse_zone(z).unwrap())
+        .collect();
+    tui::now(view, zones);
+}
+
+fn game(matches: &ArgMatches) {
+    let names = matches
+        .values_of("players")
+        .unwrap()
+        .map(|s| s.trim().to_owned())
+        .collect();
+    let time = parse_duration(matches.value_of("TIME").unwrap()).unwrap();
+    let duration = |name| parse_duration(matches.value_of(name).unwrap()).unwrap();
+    let control = if matches.is_present("increment") {
+        TimeControl::Fischer(duration("increment"))
+    } else if matches.is_present("bronstein") {
+        TimeControl::Bronstein(duration("bronstein"))
+    } else if matches.is_present("delay") {
+        TimeControl::Delay(duration("delay"))
     } else {
-        let duration = args.join(" ");
-        let duration: std::time::Duration = duration.parse::<humantime::Duration>().unwrap().into();
-        let duration = chrono::Duration::from_std(duration).unwrap();
-        let (h, m, s) = hms(duration);
-        tui::timer(h as u8, m as u8, s as u8);
+        TimeControl::SuddenDeath
+    };
+    tui::game(
+        Game::new(names, time, control),
+        matches.value_of("export").map(PathBuf::from),
+    );
+}
+
+fn standup(matches: &ArgMatches) {
+    let each = parse_duration(matches.value_of("each").unwrap()).unwrap();
+    let mut speakers = matches
+        .values_of("SPEAKERS")
+        .unwrap()
+        .map(|s| Speaker::parse(s, each))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| exit_with_error(e));
+    if matches.is_present("shuffle") {
+        speakers.shuffle(&mut rand::thread_rng());
+    }
+    tui::standup(speakers);
+}
+
+fn metronome(matches: &ArgMatches) {
+    let mut view = MetronomeView::new(matches.value_of("TEMPO").unwrap().parse().unwrap());
+    if let Some(pattern) = matches.value_of("pattern") {
+        view = view.with_pattern(pattern.parse().unwrap());
     }
+    if !matches.is_present("stopped") {
+        view = view.running();
+    }
+    tui::metronome(
+        view,
+        matches.is_present("bell"),
+        matches.value_of("sound").map(PathBuf::from),
+        matches.value_of("accent-sound").map(PathBuf::from),
+    );
+}
+
+/// The zones given as `ZONES`, or those in the config file
+fn zones(matches: &ArgMatches) -> Vec<Tz> {
+    match matches.values_of("ZONES") {
+        Some(zones) => zones.map(|z| parse_zone(z).unwrap()).collect(),
+        None => Config::open()
+            .unwrap_or_else(|e| exit_with_error(e))
+            .zones
+            .iter()
+            .map(|z| parse_zone(z).unwrap_or_else(|e| exit_with_error(e)))
+            .collect(),
+    }
+}
+
+fn world(matches: &ArgMatches) {
+    let mut config = Config::open().unwrap_or_else(|e| exit_with_error(e));
+    match matches.subcommand() {
+        ("add", Some(matches)) => {
+            for zone in matches.values_of("ZONES").unwrap() {
+                let name = parse_zone(zone).unwrap().name().to_owned();
+                if !config.zones.contains(&name) {
+                    config.zones.push(name);
+                }
+            }
+            config.save().unwrap_or_else(|e| exit_with_error(e));
+        }
+        ("remove", Some(matches)) => {
+            for zone in matches.values_of("ZONES").unwrap() {
+                let len = config.zones.len();
+                config.zones.retain(|z| !z.eq_ignore_ascii_case(zone));
+                if config.zones.len() == len {
+                    exit_with_error(format!("{} is not in the config file", zone));
+                }
+            }
+            config.save().unwrap_or_else(|e| exit_with_error(e));
+        }
+        _ => {
+            let zones = zones(matches);
+            if matches.is_present("list") {
+                let now = Utc::now();
+                let today = Local::now().date_naive();
+                for zone in zones {
+                    let time = now.with_timezone(&zone);
+                    println!(
+                        "{}  {:<9}  {:<5}  {:<6}  {}",
+                        time.format("%H:%M"),
+                        utc_offset(&time),
+                        time.format("%Z"),
+                        day_marker(&time, today),
+                        zone.name()
+                    );
+                }
+            } else {
+                tui::world(zones);
+            }
+        }
+    }
+}
+
+fn detach(matches: &ArgMatches) {
+    let request = match matches.values_of("DURATION") {
+        Some(words) => {
+            let input = words.collect::<Vec<_>>().join(" ");
+            let duration = TimerView::parse_duration(&input, Local::now())
+                .unwrap_or_else(|e| exit_with_error(e));
+            Request::StartTimer { duration }
+        }
+        None => Request::StartStopwatch,
+    };
+    daemon::spawn().unwrap_or_else(|e| exit_with_error(e));
+    match daemon::request(&request) {
+        Ok(Response::Started { id }) => match request {
+            Request::StartTimer { duration } => println!(
+                "Timer #{} started, ends at {}",
+                id,
+                (Local::now() + duration).format("%Y-%m-%d %H:%M:%S")
+            ),
+            _ => println!("Stopwatch #{} started", id),
+        },
+        Ok(response) => exit_with_error(format!("unexpected response: {:?}", response)),
+        Err(e) => exit_with_error(e),
+    }
+}
+
+/// Prints the state of the daemon every second, until interrupted
+fn attach_plain() {
+    loop {
+        let status = match daemon::request(&Request::Status) {
+            Ok(Response::Status(status)) => status,
+            Ok(response) => exit_with_error(format!("unexpected response: {:?}", response)),
+            Err(e) => exit_with_error(e),
+        };
+        println!("{}", Local::now().format("%H:%M:%S"));
+        for job in &status.jobs {
+            println!("  {}", job);
+        }
+        let now = Local::now();
+        for alarm in &status.alarms {
+            if let Some(ring) = alarm.next_ring(now) {
+                println!("  alarm {}  {}", ring.format("%a %H:%M"), alarm.name);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+fn export(matches: &ArgMatches) {
+    let store = SessionStore::open().unwrap_or_else(|e| exit_with_error(e));
+    let (from, to) = date_range(matches);
+    let output = matches.value_of("output");
+    let format = match matches.value_of("format") {
+        Some(format) => format.parse().unwrap(),
+        None => output
+            .and_then(Format::from_path)
+            .unwrap_or(Format::ICalendar),
+    };
+    let mut sessions: Vec<Session> = store.sessions().to_vec();
+    if matches.is_present("timers") {
+        let runs = TimerRun::load_all().unwrap_or_else(|e| exit_with_error(e));
+        sessions.extend(runs.iter().map(TimerRun::to_session));
+        sessions.sort_by_key(|s| s.start);
+    }
+    sessions.retain(|s| in_range(s, from, to));
+    let content = export::export(&sessions, format);
+    match output {
+        Some(output) => fs::write(output, content).unwrap_or_else(|e| exit_with_error(e)),
+        None => print!("{}", content),
+    }
+}
+
+fn import(matches: &ArgMatches) {
+    let path = matches.value_of("FILE").unwrap();
+    let format = match matches.value_of("format") {
+        Some(format) => format.parse().unwrap(),
+        None => Format::from_path(path).unwrap_or_else(|| {
+            exit_with_error("cannot tell the format from the file name, use --format")
+        }),
+    };
+    let content = fs::read_to_string(path).unwrap_or_else(|e| exit_with_error(e));
+    let sessions = export::import(&content, format).unwrap_or_else(|e| exit_with_error(e));
+    let mut store = SessionStore::open().unwrap_or_else(|e| exit_with_error(e));
+    let (mut imported, mut skipped) = (0, 0);
+    for session in sessions {
+        // sessions which were exported from this store, or imported before. Org-mode only keeps
+        // minutes.
+        let duplicate = store.sessions().iter().any(|s| {
+            s.project == session.project
+                && (s.start - session.start).num_seconds().abs() < 60
+                && (s.end - session.end).num_seconds().abs() < 60
+        });
+        if duplicate {
+            skipped += 1;
+        } else {
+            store.add(session).unwrap_or_else(|e| exit_with_error(e));
+            imported += 1;
+        }
+    }
+    println!(
+        "Imported {} sessions, skipped {} duplicates",
+        imported, skipped
+    );
+}
+
+/// The `--from` and `--to` options. A date without a time includes the whole day in `--to`.
+fn date_range(matches: &ArgMatches) -> (Option<DateTime<Local>>, Option<DateTime<Local>>) {
+    let from = matches.value_of("from").map(|s| parse_datetime(s).unwrap());
+    let to = matches.value_of("to").map(|s| {
+        let to = parse_datetime(s).unwrap();
+        if to.time().num_seconds_from_midnight() == 0 {
+            to + Duration::days(1)
+        } else {
+            to
+        }
+    });
+    (from, to)
+}
+
+fn format_session(session: &Session) -> String {
+    let mut line = format!(
+        "{:>4}  {}  {}  {}  {}  {}",
+        session.id,
+        session.start.format("%Y-%m-%d"),
+        session.start.format("%H:%M"),
+        session.end.format("%H:%M"),
+        session.elapsed.hhmmss(),
+        session.project
+    );
+    for tag in &session.tags {
+        line.push_str(&format!(" +{}", tag));
+    }
+    line
+}
+
+fn parse_tags<'a, I: Iterator<Item = &'a str>>(tags: Option<I>) -> Vec<String> {
+    tags.into_iter()
+        .flatten()
+        .map(|tag| tag.trim_start_matches('+').to_owned())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn parse_id(id: &str) -> u64 {
+    id.parse()
+        .unwrap_or_else(|_| exit_with_error(format!("invalid session id: {}", id)))
+}
+
+fn exit_with_error<E: Display>(e: E) -> ! {
+    eprintln!("error: {}", e);
+    std::process::exit(1)
 }
 
-fn hms(duration: chrono::Duration) -> (i64, i64, i64) {
-    let s = duration.num_seconds();
-    let (h, s) = (s / 3600, s % 3600);
-    let (m, s) = (s / 60, s % 60);
-    (h, m, s)
+fn parse_duration(s: &str) -> Result<chrono::Duration, String> {
+    let duration: std::time::Duration = s
+        .parse::<humantime::Duration>()
+        .map_err(|e| e.to_string())?
+        .into();
+    chrono::Duration::from_std(duration).map_err(|e| e.to_string())
 }
@@ -0,0 +1,325 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Metronome
+//!
+//! A tempo (beats per minute, or an interval such as every 30 seconds), a pattern of accented,
+//! normal and silent beats, and the schedule of the beats.
+//!
+//! Beats are scheduled against the monotonic clock (`Instant`): the n-th beat is due at the start
+//! plus n intervals, rather than one interval after the previous beat was noticed, so the delay
+//! in noticing a beat never adds up.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The slowest and fastest tempos, in beats per minute
+const MIN_BPM: f64 = 1.0;
+const MAX_BPM: f64 = 1000.0;
+/// Taps further apart than this start a new tap tempo
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+/// The number of taps the tap tempo is averaged over
+const MAX_TAPS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tempo {
+    Bpm(f64),
+    Every(Duration),
+}
+
+impl Tempo {
+    /// The time between two beats
+    pub fn interval(self) -> Duration {
+        match self {
+            Tempo::Bpm(bpm) => Duration::from_secs_f64(60.0 / bpm),
+            Tempo::Every(interval) => interval,
+        }
+    }
+
+    /// Speeds up by `steps` beats per minute, or seconds for an interval (slows down if negative),
+    /// within the limits of `from_str()`
+    pub fn faster(self, steps: i32) -> Self {
+        match self {
+            Tempo::Bpm(bpm) => Tempo::Bpm((bpm + steps as f64).clamp(MIN_BPM, MAX_BPM)),
+            Tempo::Every(interval) => {
+                let secs = (interval.as_secs_f64() - steps as f64).max(60.0 / MAX_BPM);
+                Tempo::Every(Duration::from_secs_f64(secs))
+            }
+        }
+    }
+}
+
+impl FromStr for Tempo {
+    type Err = String;
+
+    /// Parses beats per minute, e.g. `120bpm` or `120`, or an interval, e.g. `30s` or `1m`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let bpm = s
+            .strip_suffix("bpm")
+            .or_else(|| s.strip_suffix("BPM"))
+            .unwrap_or(s)
+            .trim();
+        if let Ok(bpm) = bpm.parse::<f64>() {
+            return if (MIN_BPM..=MAX_BPM).contains(&bpm) {
+                Ok(Tempo::Bpm(bpm))
+            } else {
+                Err(format!(
+                    "the tempo must be between {} and {} bpm",
+                    MIN_BPM, MAX_BPM
+                ))
+            };
+        }
+        let interval = *s
+            .parse::<humantime::Duration>()
+            .map_err(|e| format!("invalid tempo {}: {}", s, e))?;
+        if interval < Duration::from_secs_f64(60.0 / MAX_BPM) {
+            return Err(format!(
+                "the interval must be at least {}ms",
+                60000.0 / MAX_BPM
+            ));
+        }
+        Ok(Tempo::Every(interval))
+    }
+}
+
+impl fmt::Display for Tempo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tempo::Bpm(bpm) => write!(f, "{:.0} BPM", bpm),
+            Tempo::Every(interval) => {
+                write!(f, "every {}", humantime::format_duration(*interval))
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Beat {
+    Accent,
+    Normal,
+    /// A silent beat
+    Rest,
+}
+
+/// The beats of a bar, e.g. `X...` for 4/4 with the first beat accented (`X` is an accented
+/// beat, `x` or `.` a normal one and `-` a silent one)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern(pub Vec<Beat>);
+
+impl Default for Pattern {
+    /// A single, normal beat, i.e. no accents
+    fn default() -> Self {
+        Pattern(vec![Beat::Normal])
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let beats = s
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                'X' | '>' => Ok(Beat::Accent),
+                'x' | '.' => Ok(Beat::Normal),
+                '-' => Ok(Beat::Rest),
+                _ => Err(format!("invalid beat '{}' (expected X, x, . or -)", c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if beats.is_empty() {
+            return Err("the pattern is empty".to_owned());
+        }
+        Ok(Pattern(beats))
+    }
+}
+
+impl Pattern {
+    /// The beat at `n`, counting from 0 and repeating the pattern
+    pub fn beat(&self, n: u64) -> Beat {
+        self.0[(n % self.0.len() as u64) as usize]
+    }
+}
+
+/// When the beats are due
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    interval: Duration,
+    /// A beat which is due at `anchor`, from which the following beats are counted
+    anchor: Instant,
+    anchor_beat: u64,
+    /// The number of the next beat, counting from 0
+    next: u64,
+}
+
+impl Schedule {
+    /// The first beat is due at `start`
+    pub fn new(start: Instant, interval: Duration) -> Self {
+        Self {
+            interval,
+            anchor: start,
+            anchor_beat: 0,
+            next: 0,
+        }
+    }
+
+    /// When the `n`th beat is due
+    fn due_at(&self, n: u64) -> Instant {
+        let intervals = (n - self.anchor_beat) as f64;
+        self.anchor + self.interval.mul_f64(intervals)
+    }
+
+    /// The number of the latest beat which has become due since the last call, if any. Beats
+    /// which were missed (e.g. when the computer was suspended) are skipped.
+    pub fn poll(&mut self, now: Instant) -> Option<u64> {
+        if now < self.due_at(self.next) {
+            return None;
+        }
+        let elapsed = now.duration_since(self.anchor).as_secs_f64();
+        let beat = self.anchor_beat + (elapsed / self.interval.as_secs_f64()) as u64;
+        self.next = beat.max(self.next) + 1;
+        Some(self.next - 1)
+    }
+
+    /// Changes the tempo from the latest beat on, so that the next beat is due one new interval
+    /// after it
+    pub fn set_interval(&mut self, interval: Duration) {
+        if self.next > 0 {
+            self.anchor = self.due_at(self.next - 1);
+            self.anchor_beat = self.next - 1;
+        }
+        self.interval = interval;
+    }
+}
+
+/// Averages the intervals between taps
+#[derive(Clone, Debug, Default)]
+pub struct TapTempo {
+    taps: Vec<Instant>,
+}
+
+impl TapTempo {
+    /// Records a tap, and returns the tempo once there are at least two taps. A tap more than 2
+    /// seconds after the previous one starts over.
+    pub fn tap(&mut self, now: Instant) -> Option<Tempo> {
+        if let Some(&last) = self.taps.last() {
+            if now.duration_since(last) > TAP_TIMEOUT {
+                self.taps.clear();
+            }
+        }
+        self.taps.push(now);
+        if self.taps.len() > MAX_TAPS {
+            self.taps.remove(0);
+        }
+        let first = *self.taps.first()?;
+        let intervals = self.taps.len() as u32 - 1;
+        if intervals == 0 {
+            return None;
+        }
+        let interval = now.duration_since(first) / intervals;
+        let bpm = (60.0 / interval.as_secs_f64()).clamp(MIN_BPM, MAX_BPM);
+        Some(Tempo::Bpm(bpm))
+    }
+
+    /// The number of taps so far, or 0 if the last one was too long ago to count
+    pub fn count(&self, now: Instant) -> usize {
+        match self.taps.last() {
+            Some(&last) if now.duration_since(last) <= TAP_TIMEOUT => self.taps.len(),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn tempo() {
+        assert_eq!("120".parse(), Ok(Tempo::Bpm(120.0)));
+        assert_eq!("90 bpm".parse(), Ok(Tempo::Bpm(90.0)));
+        assert_eq!("30s".parse(), Ok(Tempo::Every(ms(30_000))));
+        assert!("0".parse::<Tempo>().is_err());
+        assert!("1001bpm".parse::<Tempo>().is_err());
+        assert!("10ms".parse::<Tempo>().is_err());
+        assert_eq!(Tempo::Bpm(120.0).interval(), ms(500));
+        assert_eq!(Tempo::Bpm(995.0).faster(10), Tempo::Bpm(MAX_BPM));
+        assert_eq!(Tempo::Bpm(5.0).faster(-10), Tempo::Bpm(MIN_BPM));
+        assert_eq!(
+            Tempo::Every(ms(30_000)).faster(-5),
+            Tempo::Every(ms(35_000))
+        );
+        assert_eq!(Tempo::Every(ms(2_000)).faster(5), Tempo::Every(ms(60)));
+    }
+
+    #[test]
+    fn pattern() {
+        let pattern: Pattern = "X.x-".parse().unwrap();
+        assert_eq!(
+            pattern,
+            Pattern(vec![Beat::Accent, Beat::Normal, Beat::Normal, Beat::Rest])
+        );
+        assert_eq!(pattern.beat(4), Beat::Accent);
+        assert_eq!(pattern.beat(7), Beat::Rest);
+        assert!("X.o".parse::<Pattern>().is_err());
+        assert!(" ".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn schedule() {
+        let start = Instant::now();
+        let mut schedule = Schedule::new(start, ms(500));
+        assert_eq!(schedule.poll(start), Some(0));
+        assert_eq!(schedule.poll(start + ms(499)), None);
+        assert_eq!(schedule.poll(start + ms(500)), Some(1));
+        assert_eq!(schedule.poll(start + ms(700)), None);
+        // the beats at 1000ms and 1500ms were missed
+        assert_eq!(schedule.poll(start + ms(2100)), Some(4));
+        assert_eq!(schedule.poll(start + ms(2400)), None);
+        // from the beat at 2000ms on, every 250ms
+        schedule.set_interval(ms(250));
+        assert_eq!(schedule.poll(start + ms(2200)), None);
+        assert_eq!(schedule.poll(start + ms(2250)), Some(5));
+        assert_eq!(schedule.poll(start + ms(2500)), Some(6));
+    }
+
+    #[test]
+    fn tap_tempo() {
+        let start = Instant::now();
+        let mut taps = TapTempo::default();
+        assert_eq!(taps.tap(start), None);
+        assert_eq!(taps.tap(start + ms(500)), Some(Tempo::Bpm(120.0)));
+        assert_eq!(taps.tap(start + ms(1100)), Some(Tempo::Bpm(60.0 / 0.55)));
+        assert_eq!(taps.count(start + ms(1100)), 3);
+        assert_eq!(taps.count(start + ms(3200)), 0);
+        // too long after the last tap: starts over
+        assert_eq!(taps.tap(start + ms(3200)), None);
+        assert_eq!(taps.count(start + ms(3200)), 1);
+        // averaged over the last 8 taps only
+        for i in 1..=10 {
+            taps.tap(start + ms(3200 + 100 * i));
+        }
+        assert_eq!(taps.count(start + ms(4200)), MAX_TAPS);
+        assert_eq!(taps.tap(start + ms(4300)), Some(Tempo::Bpm(600.0)));
+    }
+}
@@ -1,6 +1,10 @@
 use crate::utils::BoxedError;
 use notify_rust::Notification;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
 use std::result::Result;
+use std::thread;
 
 pub fn notify(msg: &str) -> Result<(), BoxedError> {
     Notification::new()
@@ -10,3 +14,29 @@ pub fn notify(msg: &str) -> Result<(), BoxedError> {
         .show()?;
     Ok(())
 }
+
+/// Rings the terminal bell
+pub fn bell() {
+    print!("\x07");
+    let _ = io::stdout().flush();
+}
+
+/// Plays a sound file in the background, with `afplay` on macOS and `paplay` (PulseAudio)
+/// elsewhere
+pub fn play(path: &Path) {
+    let player = if cfg!(target_os = "macos") {
+        "afplay"
+    } else {
+        "paplay"
+    };
+    let child = Command::new(player)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = child {
+        // reap the player when it is done
+        thread::spawn(move || child.wait());
+    }
+}
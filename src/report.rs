@@ -0,0 +1,248 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Time Reports
+//!
+//! Total tracked time of the sessions in `track`, grouped by day, week, project or tag. A session
+//! counts towards the day it started on, and towards each of its tags.
+
+use crate::track::Session;
+use chrono::{DateTime, Datelike, Duration, Local};
+use hhmmss::Hhmmss;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Project,
+    Tag,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 4] = [GroupBy::Day, GroupBy::Week, GroupBy::Project, GroupBy::Tag];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupBy::Day => "day",
+            GroupBy::Week => "week",
+            GroupBy::Project => "project",
+            GroupBy::Tag => "tag",
+        }
+    }
+
+    /// The groups a session counts towards
+    fn keys(self, session: &Session) -> Vec<String> {
+        match self {
+            GroupBy::Day => vec![session.start.format("%Y-%m-%d %a").to_string()],
+            GroupBy::Week => {
+                let week = session.start.iso_week();
+                vec![format!("{}-W{:02}", week.year(), week.week())]
+            }
+            GroupBy::Project => vec![session.project.clone()],
+            GroupBy::Tag if session.tags.is_empty() => vec![String::from("(no tag)")],
+            GroupBy::Tag => session.tags.iter().map(|tag| format!("+{}", tag)).collect(),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GroupBy::ALL
+            .iter()
+            .copied()
+            .find(|g| g.name() == s)
+            .ok_or_else(|| format!("cannot group by {}", s))
+    }
+}
+
+pub struct Report {
+    pub group_by: GroupBy,
+    /// The total tracked time of each group. Days and weeks are in chronological order, projects
+    /// and tags are sorted by time, longest first.
+    pub rows: Vec<(String, Duration)>,
+    /// The total tracked time of all sessions (which can be less than the sum of the rows when
+    /// grouping by tag)
+    pub total: Duration,
+}
+
+impl Report {
+    pub fn new<'a, I: IntoIterator<Item = &'a Session>>(sessions: I, group_by: GroupBy) -> Self {
+        let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+        let mut total = Duration::zero();
+        for session in sessions {
+            total += session.elapsed;
+            for key in group_by.keys(session) {
+                *totals.entry(key).or_insert_with(Duration::zero) += session.elapsed;
+            }
+        }
+        let mut rows: Vec<(String, Duration)> = totals.into_iter().collect();
+        if let GroupBy::Project | GroupBy::Tag = group_by {
+            rows.sort_by_key(|&(_, time)| Reverse(time));
+        }
+        Self {
+            group_by,
+            rows,
+            total,
+        }
+    }
+
+    /// Formats the report as a table with a bar chart, whose longest bar is `bar_width` wide
+    pub fn to_table(&self, bar_width: usize) -> String {
+        let key_width = self
+            .rows
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(self.group_by.name().len())
+            .max(5);
+        let max = self
+            .rows
+            .iter()
+            .map(|(_, time)| *time)
+            .max()
+            .unwrap_or_else(Duration::zero);
+        let mut table = format!(
+            "{:<width$}  {:>9}\n",
+            capitalize(self.group_by.name()),
+            "Tracked",
+            width = key_width
+        );
+        for (key, time) in &self.rows {
+            let bar = if max > Duration::zero() {
+                (time.num_milliseconds() * bar_width as i64 / max.num_milliseconds()) as usize
+            } else {
+                0
+            };
+            table.push_str(&format!(
+                "{:<width$}  {:>9}  {}\n",
+                key,
+                time.hhmmss(),
+                "█".repeat(bar),
+                width = key_width
+            ));
+        }
+        table.push_str(&format!(
+            "{:<width$}  {:>9}\n",
+            "Total",
+            self.total.hhmmss(),
+            width = key_width
+        ));
+        table
+    }
+}
+
+/// Whether a session started between `from` (inclusive) and `to` (exclusive)
+pub fn in_range(
+    session: &Session,
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+) -> bool {
+    from.map_or(true, |from| session.start >= from) && to.map_or(true, |to| session.start < to)
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session(project: &str, tags: &[&str], day: u32, minutes: i64) -> Session {
+        let start = Local
+            .with_ymd_and_hms(2020, 10, day, 9, 0, 0)
+            .earliest()
+            .unwrap();
+        Session {
+            id: 0,
+            project: project.to_owned(),
+            tags: tags.iter().map(|&tag| tag.to_owned()).collect(),
+            start,
+            end: start + Duration::minutes(minutes),
+            elapsed: Duration::minutes(minutes),
+            laps: Vec::new(),
+        }
+    }
+
+    fn rows(report: &Report) -> Vec<(&str, i64)> {
+        report
+            .rows
+            .iter()
+            .map(|(key, time)| (key.as_str(), time.num_minutes()))
+            .collect()
+    }
+
+    #[test]
+    fn groups() {
+        let sessions = vec![
+            session("clock", &["rust"], 11, 30),
+            session("docs", &[], 12, 60),
+            session("clock", &["rust", "tui"], 12, 45),
+        ];
+        let by_day = Report::new(&sessions, GroupBy::Day);
+        assert_eq!(
+            rows(&by_day),
+            vec![("2020-10-11 Sun", 30), ("2020-10-12 Mon", 105)]
+        );
+        assert_eq!(by_day.total, Duration::minutes(135));
+        let by_week = Report::new(&sessions, GroupBy::Week);
+        assert_eq!(rows(&by_week), vec![("2020-W41", 30), ("2020-W42", 105)]);
+        let by_project = Report::new(&sessions, GroupBy::Project);
+        assert_eq!(rows(&by_project), vec![("clock", 75), ("docs", 60)]);
+        let by_tag = Report::new(&sessions, GroupBy::Tag);
+        assert_eq!(
+            rows(&by_tag),
+            vec![("+rust", 75), ("(no tag)", 60), ("+tui", 45)]
+        );
+        assert_eq!(by_tag.total, Duration::minutes(135));
+    }
+
+    #[test]
+    fn table() {
+        let sessions = vec![session("clock", &[], 11, 90), session("docs", &[], 12, 45)];
+        assert_eq!(
+            Report::new(&sessions, GroupBy::Project).to_table(4),
+            "Project    Tracked\n\
+             clock     01:30:00  ████\n\
+             docs      00:45:00  ██\n\
+             Total     02:15:00\n"
+        );
+        assert_eq!("week".parse(), Ok(GroupBy::Week));
+        assert!("month".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn range() {
+        let session = session("clock", &[], 12, 30);
+        let day = |day| Local.with_ymd_and_hms(2020, 10, day, 0, 0, 0).earliest();
+        assert!(in_range(&session, None, None));
+        assert!(in_range(&session, day(12), day(13)));
+        assert!(!in_range(&session, day(13), None));
+        assert!(!in_range(&session, None, day(12)));
+    }
+}
@@ -0,0 +1,666 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Speedrun Splits
+//!
+//! A list of segments with the personal best (PB) and the best time of each segment, which can be
+//! loaded from and saved to a LiveSplit `.lss` file or a simple TOML file:
+//!
+//! ```toml
+//! game = "Celeste"
+//! category = "Any%"
+//! attempts = 12
+//!
+//! [[segments]]
+//! name = "Forsaken City"
+//! personal_best = "00:05:12.345" # split time, i.e. from the start of the run
+//! best_segment = "00:05:01.000"
+//! ```
+//!
+//! When saving to a `.lss` file that the splits were loaded from, only the PB split times, the best
+//! segment times and the attempt count are updated, so that everything else LiveSplit stores in
+//! the file is kept.
+
+use crate::utils::BoxedError;
+use chrono::Duration;
+use hhmmss::Hhmmss;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default)]
+pub struct Segment {
+    pub name: String,
+    /// The split time (counted from the start of the run) of this segment in the personal best run
+    pub personal_best: Option<Duration>,
+    /// The fastest this segment has ever been completed
+    pub best_segment: Option<Duration>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Splits {
+    pub game: String,
+    pub category: String,
+    pub attempts: u32,
+    pub segments: Vec<Segment>,
+    lss_source: Option<String>, // the original content if loaded from a `.lss` file
+}
+
+/// The outcome of an attempt, as recorded by `Splits::record_run`
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    /// The time of each completed segment. Shorter than the list of segments if the run was reset.
+    pub segment_times: Vec<Duration>,
+    pub personal_best: bool,
+    /// The number of segments which were completed faster than ever before
+    pub best_segments: usize,
+}
+
+impl Splits {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BoxedError> {
+        let content = fs::read_to_string(&path)?;
+        if is_lss(path.as_ref()) {
+            Self::from_lss(content)
+        } else {
+            Self::from_toml(&content)
+        }
+    }
+
+    /// Saves the splits as `.lss` or TOML, depending on the extension of `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BoxedError> {
+        let content = if is_lss(path.as_ref()) {
+            self.to_lss()?
+        } else {
+            self.to_toml()?
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn title(&self) -> String {
+        match (self.game.is_empty(), self.category.is_empty()) {
+            (false, false) => format!("{} - {}", self.game, self.category),
+            (false, true) => self.game.clone(),
+            (true, false) => self.category.clone(),
+            (true, true) => String::from("Splits"),
+        }
+    }
+
+    /// The final time of the personal best run
+    pub fn personal_best(&self) -> Option<Duration> {
+        self.segments
+            .last()
+            .and_then(|segment| segment.personal_best)
+    }
+
+    /// The sum of the best times of all segments, i.e. the best possible time. `None` unless every
+    /// segment has a best time.
+    pub fn sum_of_best(&self) -> Option<Duration> {
+        self.segments
+            .iter()
+            .try_fold(Duration::zero(), |acc, segment| {
+                Some(acc + segment.best_segment?)
+            })
+    }
+
+    /// The sum of the best times of the first `n` segments
+    pub fn sum_of_best_until(&self, n: usize) -> Option<Duration> {
+        self.segments[..n]
+            .iter()
+            .try_fold(Duration::zero(), |acc, segment| {
+                Some(acc + segment.best_segment?)
+            })
+    }
+
+    /// Counts an attempt and updates the best segments, and the personal best if the run was
+    /// completed faster than before.
+    pub fn record_run(&mut self, segment_times: Vec<Duration>) -> RunResult {
+        self.attempts += 1;
+        let mut best_segments = 0;
+        for (segment, &time) in self.segments.iter_mut().zip(segment_times.iter()) {
+            if segment.best_segment.map_or(true, |best| time < best) {
+                segment.best_segment = Some(time);
+                best_segments += 1;
+            }
+        }
+        let completed = !self.segments.is_empty() && segment_times.len() == self.segments.len();
+        let final_time = segment_times
+            .iter()
+            .fold(Duration::zero(), |acc, &time| acc + time);
+        let personal_best = completed && self.personal_best().map_or(true, |pb| final_time < pb);
+        if personal_best {
+            let mut split = Duration::zero();
+            for (segment, &time) in self.segments.iter_mut().zip(segment_times.iter()) {
+                split += time;
+                segment.personal_best = Some(split);
+            }
+        }
+        RunResult {
+            segment_times,
+            personal_best,
+            best_segments,
+        }
+    }
+
+    fn from_toml(content: &str) -> Result<Self, BoxedError> {
+        let file: TomlSplits = toml::from_str(content)?;
+        let mut segments = Vec::new();
+        for segment in file.segments {
+            segments.push(Segment {
+                name: segment.name,
+                personal_best: segment
+                    .personal_best
+                    .as_deref()
+                    .map(parse_time)
+                    .transpose()?,
+                best_segment: segment
+                    .best_segment
+                    .as_deref()
+                    .map(parse_time)
+                    .transpose()?,
+            });
+        }
+        Ok(Self {
+            game: file.game,
+            category: file.category,
+            attempts: file.attempts,
+            segments,
+            lss_source: None,
+        })
+    }
+
+    fn to_toml(&self) -> Result<String, BoxedError> {
+        let file = TomlSplits {
+            game: self.game.clone(),
+            category: self.category.clone(),
+            attempts: self.attempts,
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| TomlSegment {
+                    name: segment.name.clone(),
+                    personal_best: segment.personal_best.map(|t| t.hhmmssxxx()),
+                    best_segment: segment.best_segment.map(|t| t.hhmmssxxx()),
+                })
+                .collect(),
+        };
+        Ok(toml::to_string(&file)?)
+    }
+
+    fn from_lss(content: String) -> Result<Self, BoxedError> {
+        let mut splits = Splits::default();
+        {
+            let doc = roxmltree::Document::parse(&content)?;
+            let run = doc.root_element();
+            if !run.has_tag_name("Run") {
+                return Err("not a LiveSplit splits file".into());
+            }
+            splits.game = child_text(run, "GameName").unwrap_or_default().to_owned();
+            splits.category = child_text(run, "CategoryName")
+                .unwrap_or_default()
+                .to_owned();
+            splits.attempts = child_text(run, "AttemptCount")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or(0);
+            for node in lss_segments(run) {
+                splits.segments.push(Segment {
+                    name: child_text(node, "Name").unwrap_or_default().to_owned(),
+                    personal_best: lss_pb_split_time(node)
+                        .and_then(|n| child_text(n, "RealTime"))
+                        .map(parse_time)
+                        .transpose()?,
+                    best_segment: child(node, "BestSegmentTime")
+                        .and_then(|n| child_text(n, "RealTime"))
+                        .map(parse_time)
+                        .transpose()?,
+                });
+            }
+        }
+        splits.lss_source = Some(content);
+        Ok(splits)
+    }
+
+    fn to_lss(&self) -> Result<String, BoxedError> {
+        match &self.lss_source {
+            Some(source) => self.patch_lss(source),
+            None => Ok(self.new_lss()),
+        }
+    }
+
+    /// Writes the current times into the original `.lss` file content, adding the elements for
+    /// them where the file has none yet (e.g. before the first completed run)
+    fn patch_lss(&self, source: &str) -> Result<String, BoxedError> {
+        let doc = roxmltree::Document::parse(source)?;
+        let run = doc.root_element();
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        let attempt_count = format!("<AttemptCount>{}</AttemptCount>", self.attempts);
+        match child(run, "AttemptCount") {
+            Some(node) => edits.push((node.range(), attempt_count)),
+            None => {
+                let sibling = last_child(run, &["GameName", "CategoryName", "Offset"])
+                    .ok_or("the splits file has no game or category name")?;
+                edits.push(insert_after(source, sibling, &attempt_count));
+            }
+        }
+        for (node, segment) in lss_segments(run).zip(self.segments.iter()) {
+            let split_times = child(node, "SplitTimes");
+            if let Some(time) = segment.personal_best {
+                let pb = format!(
+                    "<SplitTime name=\"Personal Best\">{}</SplitTime>",
+                    lss_real_time(time)
+                );
+                match (lss_pb_split_time(node), split_times) {
+                    (Some(split_time), _) => match child(split_time, "RealTime") {
+                        Some(real_time) => edits.push((real_time.range(), lss_real_time(time))),
+                        None => edits.push((split_time.range(), pb)),
+                    },
+                    (None, Some(split_times)) => {
+                        match split_times.children().rev().find(|n| n.is_element()) {
+                            Some(sibling) => edits.push(insert_after(source, sibling, &pb)),
+                            None => edits.push((
+                                split_times.range(),
+                                format!("<SplitTimes>{}</SplitTimes>", pb),
+                            )),
+                        }
+                    }
+                    (None, None) => {
+                        let sibling = last_child(node, &["Name", "Icon"])
+                            .ok_or_else(|| format!("segment {} has no name", segment.name))?;
+                        let split_times = format!("<SplitTimes>{}</SplitTimes>", pb);
+                        edits.push(insert_after(source, sibling, &split_times));
+                    }
+                }
+            }
+            if let Some(time) = segment.best_segment {
+                let best_segment =
+                    format!("<BestSegmentTime>{}</BestSegmentTime>", lss_real_time(time));
+                match child(node, "BestSegmentTime") {
+                    Some(best) => match child(best, "RealTime") {
+                        Some(real_time) => edits.push((real_time.range(), lss_real_time(time))),
+                        None => edits.push((best.range(), best_segment)),
+                    },
+                    None => {
+                        // after the split times, which may have just been added after the name
+                        let sibling = split_times
+                            .or_else(|| last_child(node, &["Name", "Icon"]))
+                            .ok_or_else(|| format!("segment {} has no name", segment.name))?;
+                        edits.push(insert_after(source, sibling, &best_segment));
+                    }
+                }
+            }
+        }
+        // stable, so that elements inserted at the same place stay in order
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut patched = source.to_owned();
+        for (range, replacement) in edits.into_iter().rev() {
+            patched.replace_range(range, &replacement);
+        }
+        Ok(patched)
+    }
+
+    /// Creates a minimal `.lss` file
+    fn new_lss(&self) -> String {
+        let mut lss = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        lss.push_str("<Run version=\"1.7.0\">\n");
+        lss.push_str("  <GameIcon />\n");
+        lss.push_str(&format!(
+            "  <GameName>{}</GameName>\n",
+            escape_xml(&self.game)
+        ));
+        lss.push_str(&format!(
+            "  <CategoryName>{}</CategoryName>\n",
+            escape_xml(&self.category)
+        ));
+        lss.push_str("  <Offset>00:00:00</Offset>\n");
+        lss.push_str(&format!(
+            "  <AttemptCount>{}</AttemptCount>\n",
+            self.attempts
+        ));
+        lss.push_str("  <AttemptHistory />\n");
+        lss.push_str("  <Segments>\n");
+        for segment in &self.segments {
+            lss.push_str("    <Segment>\n");
+            lss.push_str(&format!(
+                "      <Name>{}</Name>\n",
+                escape_xml(&segment.name)
+            ));
+            lss.push_str("      <Icon />\n");
+            lss.push_str("      <SplitTimes>\n");
+            match segment.personal_best {
+                Some(time) => lss.push_str(&format!(
+                    "        <SplitTime name=\"Personal Best\">{}</SplitTime>\n",
+                    lss_real_time(time)
+                )),
+                None => lss.push_str("        <SplitTime name=\"Personal Best\" />\n"),
+            }
+            lss.push_str("      </SplitTimes>\n");
+            match segment.best_segment {
+                Some(time) => lss.push_str(&format!(
+                    "      <BestSegmentTime>{}</BestSegmentTime>\n",
+                    lss_real_time(time)
+                )),
+                None => lss.push_str("      <BestSegmentTime />\n"),
+            }
+            lss.push_str("      <SegmentHistory />\n");
+            lss.push_str("    </Segment>\n");
+        }
+        lss.push_str("  </Segments>\n");
+        lss.push_str("  <AutoSplitterSettings />\n");
+        lss.push_str("</Run>\n");
+        lss
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TomlSplits {
+    #[serde(default)]
+    game: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    attempts: u32,
+    segments: Vec<TomlSegment>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TomlSegment {
+    name: String,
+    personal_best: Option<String>,
+    best_segment: Option<String>,
+}
+
+fn is_lss(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("lss"))
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    tag: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag).and_then(|n| n.text()).map(str::trim)
+}
+
+/// The last child element of `node` with one of the tags
+fn last_child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    tags: &[&str],
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children()
+        .rev()
+        .find(|n| tags.iter().any(|&tag| n.has_tag_name(tag)))
+}
+
+/// An edit which inserts `element` after `sibling`, on a new line with the same indentation
+fn insert_after(source: &str, sibling: roxmltree::Node, element: &str) -> (Range<usize>, String) {
+    let Range { start, end } = sibling.range();
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &source[line_start..start];
+    let indent = if indent.trim().is_empty() { indent } else { "" };
+    (end..end, format!("\n{}{}", indent, element))
+}
+
+fn lss_segments<'a, 'input: 'a>(
+    run: roxmltree::Node<'a, 'input>,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    child(run, "Segments")
+        .into_iter()
+        .flat_map(|segments| segments.children())
+        .filter(|n| n.has_tag_name("Segment"))
+}
+
+fn lss_pb_split_time<'a, 'input>(
+    segment: roxmltree::Node<'a, 'input>,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    child(segment, "SplitTimes")?
+        .children()
+        .find(|n| n.has_tag_name("SplitTime") && n.attribute("name") == Some("Personal Best"))
+}
+
+/// Formats a time the way LiveSplit does, e.g. `<RealTime>01:02:03.4560000</RealTime>`
+fn lss_real_time(time: Duration) -> String {
+    let ms = time.num_milliseconds();
+    let (s, ms) = (ms / 1000, ms % 1000);
+    let (m, s) = (s / 60, s % 60);
+    let (h, m) = (m / 60, m % 60);
+    let (d, h) = (h / 24, h % 24);
+    let days = if d > 0 {
+        format!("{}.", d)
+    } else {
+        String::new()
+    };
+    format!(
+        "<RealTime>{}{:02}:{:02}:{:02}.{:03}0000</RealTime>",
+        days, h, m, s, ms
+    )
+}
+
+/// Parses times like `01:02:03.456`, `02:03.456`, or LiveSplit's `1.01:02:03.4560000` (with days)
+fn parse_time(s: &str) -> Result<Duration, BoxedError> {
+    let invalid = || -> BoxedError { format!("invalid time: {}", s).into() };
+    let s = s.trim();
+    let (days, rest) = match (s.find('.'), s.find(':')) {
+        (Some(dot), Some(colon)) if dot < colon => {
+            (s[..dot].parse().map_err(|_| invalid())?, &s[dot + 1..])
+        }
+        _ => (0, s),
+    };
+    let (hms, fraction) = match rest.find('.') {
+        Some(dot) => (&rest[..dot], &rest[dot + 1..]),
+        None => (rest, ""),
+    };
+    let mut seconds: i64 = 0;
+    for part in hms.split(':') {
+        seconds = seconds * 60 + part.parse::<i64>().map_err(|_| invalid())?;
+    }
+    let mut ms = 0;
+    for (i, c) in fraction.chars().take(3).enumerate() {
+        ms += c.to_digit(10).ok_or_else(invalid)? as i64 * 10i64.pow(2 - i as u32);
+    }
+    Ok(Duration::days(days) + Duration::seconds(seconds) + Duration::milliseconds(ms))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: i64) -> Duration {
+        Duration::milliseconds(ms)
+    }
+
+    fn splits() -> Splits {
+        Splits {
+            game: "Celeste & Co".to_owned(),
+            category: "Any%".to_owned(),
+            attempts: 12,
+            segments: vec![
+                Segment {
+                    name: "Forsaken City".to_owned(),
+                    personal_best: Some(ms(312_345)),
+                    best_segment: Some(ms(301_000)),
+                },
+                Segment {
+                    name: "Old Site".to_owned(),
+                    personal_best: None,
+                    best_segment: None,
+                },
+            ],
+            lss_source: None,
+        }
+    }
+
+    fn assert_same(a: &Splits, b: &Splits) {
+        assert_eq!(a.game, b.game);
+        assert_eq!(a.category, b.category);
+        assert_eq!(a.attempts, b.attempts);
+        assert_eq!(a.segments.len(), b.segments.len());
+        for (a, b) in a.segments.iter().zip(b.segments.iter()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.personal_best, b.personal_best);
+            assert_eq!(a.best_segment, b.best_segment);
+        }
+    }
+
+    #[test]
+    fn lss_round_trip() {
+        let splits = splits();
+        let loaded = Splits::from_lss(splits.to_lss().unwrap()).unwrap();
+        assert_same(&splits, &loaded);
+    }
+
+    #[test]
+    fn lss_patch_keeps_the_rest_of_the_file() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <GameName>Celeste</GameName>
+  <CategoryName>Any%</CategoryName>
+  <AttemptCount>3</AttemptCount>
+  <Segments>
+    <Segment>
+      <Name>Forsaken City</Name>
+      <SplitTimes>
+        <SplitTime name="Personal Best">
+          <RealTime>00:05:12.3450000</RealTime>
+          <GameTime>00:05:10.0000000</GameTime>
+        </SplitTime>
+      </SplitTimes>
+      <BestSegmentTime>
+        <RealTime>00:05:01.0000000</RealTime>
+      </BestSegmentTime>
+      <SegmentHistory>
+        <Time id="1"><RealTime>00:05:20.0000000</RealTime></Time>
+      </SegmentHistory>
+    </Segment>
+  </Segments>
+</Run>
+"#;
+        let mut splits = Splits::from_lss(source.to_owned()).unwrap();
+        assert_eq!(splits.attempts, 3);
+        assert_eq!(splits.personal_best(), Some(ms(312_345)));
+
+        let result = splits.record_run(vec![ms(300_000)]);
+        assert!(result.personal_best);
+        assert_eq!(result.best_segments, 1);
+        let patched = splits.to_lss().unwrap();
+        assert!(patched.contains("<AttemptCount>4</AttemptCount>"));
+        assert!(patched.contains("<GameTime>00:05:10.0000000</GameTime>"));
+        assert!(patched.contains(r#"<Time id="1"><RealTime>00:05:20.0000000</RealTime></Time>"#));
+        assert_same(&splits, &Splits::from_lss(patched).unwrap());
+    }
+
+    #[test]
+    fn lss_patch_adds_missing_times() {
+        // as LiveSplit writes a new file, and with segments lacking the elements altogether
+        let source = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <GameName>Celeste</GameName>
+  <CategoryName>Any%</CategoryName>
+  <Segments>
+    <Segment>
+      <Name>Forsaken City</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best" />
+      </SplitTimes>
+      <BestSegmentTime />
+    </Segment>
+    <Segment>
+      <Name>Old Site</Name>
+      <SplitTimes />
+    </Segment>
+    <Segment>
+      <Name>Celestial Resort</Name>
+      <SplitTimes>
+        <SplitTime name="Balanced PB" />
+      </SplitTimes>
+    </Segment>
+    <Segment>
+      <Name>Golden Ridge</Name>
+    </Segment>
+  </Segments>
+</Run>
+"#;
+        let mut splits = Splits::from_lss(source.to_owned()).unwrap();
+        assert_eq!(splits.attempts, 0);
+        assert_eq!(splits.personal_best(), None);
+
+        let result = splits.record_run(vec![ms(300_000), ms(200_000), ms(100_000), ms(50_000)]);
+        assert!(result.personal_best);
+        assert_eq!(result.best_segments, 4);
+        let patched = splits.to_lss().unwrap();
+        assert!(
+            patched.contains("<CategoryName>Any%</CategoryName>\n  <AttemptCount>1</AttemptCount>")
+        );
+        assert!(patched.contains(r#"<SplitTime name="Balanced PB" />"#));
+        let loaded = Splits::from_lss(patched).unwrap();
+        assert_same(&splits, &loaded);
+        assert_eq!(loaded.personal_best(), Some(ms(650_000)));
+        assert_eq!(loaded.sum_of_best(), Some(ms(650_000)));
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let splits = splits();
+        let loaded = Splits::from_toml(&splits.to_toml().unwrap()).unwrap();
+        assert_same(&splits, &loaded);
+    }
+
+    #[test]
+    fn record_run() {
+        let mut splits = splits();
+        // reset after the first segment, which was not the fastest
+        let result = splits.record_run(vec![ms(310_000)]);
+        assert!(!result.personal_best);
+        assert_eq!(result.best_segments, 0);
+        assert_eq!(splits.attempts, 13);
+
+        let result = splits.record_run(vec![ms(290_000), ms(60_000)]);
+        assert!(result.personal_best);
+        assert_eq!(result.best_segments, 2);
+        assert_eq!(splits.segments[0].personal_best, Some(ms(290_000)));
+        assert_eq!(splits.personal_best(), Some(ms(350_000)));
+        assert_eq!(splits.sum_of_best(), Some(ms(350_000)));
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(parse_time("01:02:03.456").unwrap(), ms(3_723_456));
+        assert_eq!(parse_time("02:03.4").unwrap(), ms(123_400));
+        assert_eq!(
+            parse_time("1.01:02:03.4560000").unwrap(),
+            ms(86_400_000 + 3_723_456)
+        );
+        assert!(parse_time("1:xx").is_err());
+        assert_eq!(
+            lss_real_time(ms(86_400_000 + 3_723_456)),
+            "<RealTime>1.01:02:03.4560000</RealTime>"
+        );
+    }
+}
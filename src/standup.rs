@@ -0,0 +1,162 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Standup Speakers
+//!
+//! A queue of speakers, each with a time box, e.g. `Alice` (with the default time box) or
+//! `Bob=3m`, and a report of how long each of them spoke compared with their time box.
+
+use chrono::Duration;
+use hhmmss::Hhmmss;
+
+#[derive(Clone, Debug)]
+pub struct Speaker {
+    pub name: String,
+    pub allotment: Duration,
+    /// How long the speaker spoke, or `None` if it was not their turn yet
+    pub spoken: Option<Duration>,
+}
+
+impl Speaker {
+    /// Parses `NAME` or `NAME=DURATION`, e.g. `Bob=3m`, with `allotment` as the default time box
+    pub fn parse(s: &str, allotment: Duration) -> Result<Self, String> {
+        let (name, allotment) = match s.find('=') {
+            Some(i) => {
+                let duration = s[i + 1..]
+                    .trim()
+                    .parse::<humantime::Duration>()
+                    .map_err(|e| format!("invalid time box for {}: {}", &s[..i], e))?;
+                (
+                    &s[..i],
+                    Duration::from_std(*duration).map_err(|e| e.to_string())?,
+                )
+            }
+            None => (s, allotment),
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("missing name: {}", s));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            allotment,
+            spoken: None,
+        })
+    }
+
+    /// How much longer than the time box the speaker spoke (negative if shorter)
+    pub fn overtime(&self) -> Option<Duration> {
+        self.spoken.map(|spoken| spoken - self.allotment)
+    }
+}
+
+/// A table of the time each speaker spoke, their time box and the difference, with the totals
+pub fn report(speakers: &[Speaker]) -> String {
+    let width = speakers
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let row = |name: &str, spoken: Option<Duration>, allotment: Duration| {
+        let (spoken, difference) = match spoken {
+            Some(spoken) => (spoken.hhmmss(), signed_hhmmss(spoken - allotment)),
+            None => ("-".to_owned(), String::new()),
+        };
+        format!(
+            "{:<width$}  {:>8}  {:>8}  {:>9}\n",
+            name,
+            spoken,
+            allotment.hhmmss(),
+            difference,
+            width = width
+        )
+    };
+
+    let mut table = format!(
+        "{:<width$}  {:>8}  {:>8}  {:>9}\n",
+        "Name",
+        "Spoke",
+        "Time box",
+        "Over",
+        width = width
+    );
+    let mut spoken = Duration::zero();
+    let mut allotment = Duration::zero();
+    for speaker in speakers {
+        table.push_str(&row(&speaker.name, speaker.spoken, speaker.allotment));
+        if let Some(t) = speaker.spoken {
+            spoken += t;
+            allotment += speaker.allotment;
+        }
+    }
+    table.push_str(&row("Total", Some(spoken), allotment));
+    table
+}
+
+/// Pretty-prints a duration in the form `+HH:MM:SS` or `-HH:MM:SS`
+fn signed_hhmmss(d: Duration) -> String {
+    if d < Duration::zero() {
+        format!("-{}", (-d).hhmmss())
+    } else {
+        format!("+{}", d.hhmmss())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let default = Duration::minutes(2);
+        let alice = Speaker::parse("Alice", default).unwrap();
+        assert_eq!(alice.name, "Alice");
+        assert_eq!(alice.allotment, default);
+        assert_eq!(alice.spoken, None);
+        let bob = Speaker::parse(" Bob = 3m 30s", default).unwrap();
+        assert_eq!(bob.name, "Bob");
+        assert_eq!(bob.allotment, Duration::seconds(210));
+        assert!(Speaker::parse("", default).is_err());
+        assert!(Speaker::parse("=3m", default).is_err());
+        assert!(Speaker::parse("Carol=soon", default).is_err());
+    }
+
+    #[test]
+    fn table() {
+        let speaker = |name: &str, allotment, spoken: Option<i64>| Speaker {
+            name: name.to_owned(),
+            allotment: Duration::seconds(allotment),
+            spoken: spoken.map(Duration::seconds),
+        };
+        let speakers = vec![
+            speaker("Alice", 120, Some(150)),
+            speaker("Bob", 180, Some(100)),
+            speaker("Christopher", 120, None),
+        ];
+        assert_eq!(speakers[0].overtime(), Some(Duration::seconds(30)));
+        assert_eq!(speakers[2].overtime(), None);
+        assert_eq!(
+            report(&speakers),
+            "Name            Spoke  Time box       Over\n\
+             Alice        00:02:30  00:02:00  +00:00:30\n\
+             Bob          00:01:40  00:03:00  -00:01:20\n\
+             Christopher         -  00:02:00           \n\
+             Total        00:04:10  00:05:00  -00:00:50\n"
+        );
+    }
+}
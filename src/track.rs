@@ -0,0 +1,428 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Time Tracking
+//!
+//! Stopwatch sessions attached to a project and tags. They are kept in an append-only log
+//! (`sessions.jsonl` in the data directory, see `utils::data_dir`): editing or deleting a session
+//! appends an entry instead of rewriting the file, and the log is replayed when it is opened. A
+//! last entry which was cut off (e.g. by a crash while it was written) is ignored, and overwritten
+//! by the next one.
+//!
+//! Runs of the countdown timer are recorded in another log, `timer_runs.jsonl`.
+
+use crate::tui::StopwatchRecord;
+use crate::utils::{data_dir, dhhmmss, duration_ms, BoxedError};
+use chrono::{DateTime, Duration, Local};
+use clock_core::timer::TimerData;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Lap {
+    #[serde(with = "duration_ms")]
+    pub time: Duration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    /// Assigned by `SessionStore::add`
+    pub id: u64,
+    pub project: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// The tracked time, which excludes pauses
+    #[serde(with = "duration_ms")]
+    pub elapsed: Duration,
+    #[serde(default)]
+    pub laps: Vec<Lap>,
+}
+
+impl Session {
+    pub fn new(
+        project: String,
+        tags: Vec<String>,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+        record: &StopwatchRecord,
+    ) -> Self {
+        let laps = record
+            .data
+            .laps
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| Lap {
+                time,
+                label: record.lap_label(i).map(str::to_owned),
+            })
+            .collect();
+        Self {
+            id: 0,
+            project,
+            tags,
+            start,
+            end,
+            elapsed: record.data.elapsed,
+            laps,
+        }
+    }
+}
+
+/// A run of the countdown timer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimerRun {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// The duration the timer was set to
+    #[serde(with = "duration_ms")]
+    pub expected: Duration,
+    /// Whether the timer counted down to zero, rather than being cancelled
+    pub finished: bool,
+}
+
+impl TimerRun {
+    /// The run of a timer which has just been stopped, after counting to zero if `finished`
+    pub fn new(data: &TimerData, finished: bool) -> Self {
+        let end = Local::now();
+        Self {
+            start: end - data.duration_actual(),
+            end,
+            expected: data.duration_expected(),
+            finished,
+        }
+    }
+
+    /// Appends the run to `timer_runs.jsonl` in the data directory
+    pub fn record(&self) -> Result<(), BoxedError> {
+        append_line(&timer_runs_path(), &serde_json::to_string(self)?)
+    }
+
+    pub fn load_all() -> Result<Vec<TimerRun>, BoxedError> {
+        let path = timer_runs_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for (i, line) in fs::read_to_string(&path)?.lines().enumerate() {
+            if !line.trim().is_empty() {
+                runs.push(
+                    serde_json::from_str(line)
+                        .map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))?,
+                );
+            }
+        }
+        Ok(runs)
+    }
+
+    /// A session representing the run, e.g. for exporting it along with tracked sessions
+    pub fn to_session(&self) -> Session {
+        Session {
+            id: 0,
+            project: format!("Timer {}", dhhmmss(self.expected)),
+            tags: vec![String::from(if self.finished {
+                "finished"
+            } else {
+                "cancelled"
+            })],
+            start: self.start,
+            end: self.end,
+            elapsed: self.end - self.start,
+            laps: Vec::new(),
+        }
+    }
+}
+
+fn timer_runs_path() -> PathBuf {
+    data_dir().join("timer_runs.jsonl")
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), BoxedError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
+    // the previous line may have been written without its line break
+    let len = file.metadata()?.len();
+    if len > 0 {
+        let mut last = [0];
+        file.seek(SeekFrom::Start(len - 1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            writeln!(file)?;
+        }
+    }
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Entry {
+    Add(Session),
+    Edit(Session),
+    Delete { id: u64 },
+}
+
+pub struct SessionStore {
+    path: PathBuf,
+    sessions: Vec<Session>,
+    next_id: u64, // ids of deleted sessions are not reused
+    /// The length of the log without a last entry which was cut off, if any
+    cut_off: Option<u64>,
+}
+
+impl SessionStore {
+    /// Opens the default store in the data directory
+    pub fn open() -> Result<Self, BoxedError> {
+        Self::open_at(data_dir().join("sessions.jsonl"))
+    }
+
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self, BoxedError> {
+        let path = path.as_ref().to_owned();
+        let mut sessions: Vec<Session> = Vec::new();
+        let mut next_id = 1;
+        let mut cut_off = None;
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let mut offset = 0;
+            for (i, line) in content.split_inclusive('\n').enumerate() {
+                let start = offset;
+                offset += line.len();
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry = match serde_json::from_str(line) {
+                    Ok(entry) => entry,
+                    // an unfinished last line
+                    Err(_) if !line.ends_with('\n') => {
+                        cut_off = Some(start as u64);
+                        break;
+                    }
+                    Err(e) => return Err(format!("{}:{}: {}", path.display(), i + 1, e).into()),
+                };
+                match entry {
+                    Entry::Add(session) => {
+                        next_id = std::cmp::max(next_id, session.id + 1);
+                        sessions.push(session);
+                    }
+                    Entry::Edit(session) => {
+                        if let Some(s) = sessions.iter_mut().find(|s| s.id == session.id) {
+                            *s = session;
+                        }
+                    }
+                    Entry::Delete { id } => sessions.retain(|s| s.id != id),
+                }
+            }
+        }
+        Ok(Self {
+            path,
+            sessions,
+            next_id,
+            cut_off,
+        })
+    }
+
+    /// All sessions, in the order they were added
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Session> {
+        self.sessions.iter().find(|s| s.id == id)
+    }
+
+    pub fn latest(&self) -> Option<&Session> {
+        self.sessions.last()
+    }
+
+    /// Saves a new session and returns its id
+    pub fn add(&mut self, mut session: Session) -> Result<u64, BoxedError> {
+        session.id = self.next_id;
+        self.append(&Entry::Add(session.clone()))?;
+        self.next_id += 1;
+        let id = session.id;
+        self.sessions.push(session);
+        Ok(id)
+    }
+
+    pub fn edit(&mut self, session: Session) -> Result<(), BoxedError> {
+        let i = self.index_of(session.id)?;
+        self.append(&Entry::Edit(session.clone()))?;
+        self.sessions[i] = session;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: u64) -> Result<(), BoxedError> {
+        let i = self.index_of(id)?;
+        self.append(&Entry::Delete { id })?;
+        self.sessions.remove(i);
+        Ok(())
+    }
+
+    fn index_of(&self, id: u64) -> Result<usize, BoxedError> {
+        self.sessions
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| format!("no session with id {}", id).into())
+    }
+
+    fn append(&mut self, entry: &Entry) -> Result<(), BoxedError> {
+        if let Some(len) = self.cut_off.take() {
+            OpenOptions::new()
+                .write(true)
+                .open(&self.path)?
+                .set_len(len)?;
+        }
+        append_line(&self.path, &serde_json::to_string(entry)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// A fresh log file in the temporary directory
+    fn log_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("clock-cli-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.jsonl", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn session(project: &str, minutes: i64) -> Session {
+        let start = Local
+            .with_ymd_and_hms(2020, 10, 12, 9, 0, 0)
+            .earliest()
+            .unwrap();
+        Session {
+            id: 0,
+            project: project.to_owned(),
+            tags: vec!["rust".to_owned()],
+            start,
+            end: start + Duration::minutes(minutes),
+            elapsed: Duration::minutes(minutes),
+            laps: vec![Lap {
+                time: Duration::minutes(minutes),
+                label: Some("all of it".to_owned()),
+            }],
+        }
+    }
+
+    fn projects(store: &SessionStore) -> Vec<(u64, &str)> {
+        store
+            .sessions()
+            .iter()
+            .map(|s| (s.id, s.project.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn replay() {
+        let path = log_path("replay");
+        let mut store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(store.add(session("clock", 30)).unwrap(), 1);
+        assert_eq!(store.add(session("docs", 60)).unwrap(), 2);
+        assert_eq!(store.add(session("chores", 15)).unwrap(), 3);
+        let mut edited = store.get(1).unwrap().clone();
+        edited.project = "clock-cli".to_owned();
+        edited.tags.clear();
+        store.edit(edited).unwrap();
+        store.delete(2).unwrap();
+        assert!(store.delete(2).is_err());
+        assert!(store.edit(session("nothing", 1)).is_err());
+
+        let store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(projects(&store), vec![(1, "clock-cli"), (3, "chores")]);
+        let edited = store.get(1).unwrap();
+        assert!(edited.tags.is_empty());
+        assert_eq!(edited.laps[0].label.as_deref(), Some("all of it"));
+        assert_eq!(store.latest().unwrap().elapsed, Duration::minutes(15));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 5);
+    }
+
+    #[test]
+    fn ids_are_not_reused() {
+        let path = log_path("ids");
+        let mut store = SessionStore::open_at(&path).unwrap();
+        store.add(session("clock", 30)).unwrap();
+        store.add(session("docs", 60)).unwrap();
+        store.delete(2).unwrap();
+        assert_eq!(store.add(session("chores", 15)).unwrap(), 3);
+
+        // not even once the latest one is deleted and the log is replayed
+        store.delete(3).unwrap();
+        let mut store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(store.add(session("errands", 5)).unwrap(), 4);
+        assert_eq!(projects(&store), vec![(1, "clock"), (4, "errands")]);
+    }
+
+    #[test]
+    fn cut_off_last_entry() {
+        let path = log_path("cut-off");
+        let mut store = SessionStore::open_at(&path).unwrap();
+        store.add(session("clock", 30)).unwrap();
+        store.add(session("docs", 60)).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() - 20]).unwrap();
+
+        let mut store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(projects(&store), vec![(1, "clock")]);
+        assert_eq!(store.add(session("chores", 15)).unwrap(), 2);
+        let store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(projects(&store), vec![(1, "clock"), (2, "chores")]);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn missing_line_break() {
+        let path = log_path("line-break");
+        let mut store = SessionStore::open_at(&path).unwrap();
+        store.add(session("clock", 30)).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.trim_end()).unwrap();
+
+        let mut store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(projects(&store), vec![(1, "clock")]);
+        store.add(session("docs", 60)).unwrap();
+        let store = SessionStore::open_at(&path).unwrap();
+        assert_eq!(projects(&store), vec![(1, "clock"), (2, "docs")]);
+    }
+
+    #[test]
+    fn corrupt_entry() {
+        let path = log_path("corrupt");
+        let mut store = SessionStore::open_at(&path).unwrap();
+        store.add(session("clock", 30)).unwrap();
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"op\":\"delete\"}\n");
+        fs::write(&path, &content).unwrap();
+        let e = SessionStore::open_at(&path).err().unwrap().to_string();
+        assert!(e.contains("corrupt.jsonl:2: "), "{}", e);
+    }
+}
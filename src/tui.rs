@@ -15,56 +15,229 @@
 // You should have received a copy of the GNU General Public License
 // along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
 
+mod alarm;
+mod analog;
+mod buttons;
+mod countdown;
+mod daemon;
+mod game;
+mod metronome;
+mod planner;
+mod splits;
+mod standup;
 mod stopwatch;
 mod timer;
-use crate::notify::notify;
-use clock_core::{stopwatch::StopwatchData, timer::TimerData};
-use cursive::{traits::*, views::Dialog, Cursive};
+mod wall;
+mod world;
+use crate::alarm::Alarm;
+use crate::config::Config;
+use crate::countdown::{CountdownEvent, EventStore};
+use crate::game::Game;
+use crate::metronome::Beat;
+use crate::notify::{bell, notify, play};
+use crate::report::{GroupBy, Report};
+use crate::splits::{RunResult, Splits};
+use crate::standup::Speaker;
+use crate::track::{Session, SessionStore, TimerRun};
+use crate::utils::dhhmmss;
+use crate::world::{describe_slot, WorkingHours};
+pub use alarm::AlarmView;
+pub use analog::AnalogClockView;
+use chrono::{Duration, Local, NaiveDate};
+use chrono_tz::Tz;
+use clock_core::timer::TimerData;
+pub use countdown::CountdownView;
+use cursive::{
+    event::Key,
+    traits::*,
+    views::{Dialog, DummyView, LinearLayout, Panel, SelectView, TextView},
+    Cursive,
+};
+pub use daemon::DaemonView;
+pub use game::GameClockView;
 use hhmmss::Hhmmss;
-pub use stopwatch::StopwatchView;
-pub use timer::TimerView;
+pub use metronome::MetronomeView;
+pub use planner::PlannerView;
+pub use splits::SplitsView;
+pub use standup::StandupView;
+use std::cell::Cell;
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+pub use stopwatch::{LapColumn, StopwatchRecord, StopwatchView};
+pub use timer::{Repeat, Threshold, TimerView};
+pub use wall::WallClockView;
+pub use world::WorldClockView;
 
-pub fn stopwatch() {
+/// Runs a stopwatch. If `target_lap` is given, the laps are compared against it, and a notification
+/// is sent whenever a lap goes over it if `notify_target_lap` is set.
+pub fn stopwatch(target_lap: Option<Duration>, notify_target_lap: bool) {
     let mut siv = cursive::default();
-    let stopwatch = StopwatchView::new();
-    siv.add_layer(
-        stopwatch
+    let mut stopwatch =
+        StopwatchView::new()
             .with_laps(8)
-            .on_stop(|s: &mut Cursive, stopwatch| s.add_layer(Dialog::info(summarize(&stopwatch))))
-            .with_name("stopwatch"),
+            .on_stop(move |s: &mut Cursive, stopwatch| {
+                s.add_layer(Dialog::info(summarize(&stopwatch, target_lap)))
+            });
+    if let Some(target) = target_lap {
+        stopwatch = stopwatch.with_target_lap(target);
+        if notify_target_lap {
+            stopwatch = stopwatch.on_target_lap_exceeded(move |_: &mut Cursive, n| {
+                let _ = notify(&format!(
+                    "Lap {} is over the target of {}",
+                    n,
+                    target.hhmmss()
+                ));
+            });
+        }
+    }
+    siv.add_layer(stopwatch.with_name("stopwatch"));
+    siv.set_fps(15);
+    siv.run();
+}
+
+/// Runs a speedrun split timer, and saves the updated splits (personal best, best segments) to
+/// `path` after each run
+pub fn splits(splits: Splits, path: PathBuf) {
+    let mut siv = cursive::default();
+    let view = SplitsView::new(splits).on_finish(move |s: &mut Cursive, splits, result| {
+        let mut msg = summarize_run(&result);
+        if let Err(e) = splits.save(&path) {
+            msg.push_str(&format!("\n\nFailed to save {}: {}", path.display(), e));
+        }
+        s.add_layer(Dialog::info(msg));
+    });
+    siv.add_layer(view);
+    siv.set_fps(15);
+    siv.run();
+}
+
+fn summarize_run(result: &RunResult) -> String {
+    let time = result
+        .segment_times
+        .iter()
+        .fold(chrono::Duration::zero(), |acc, &t| acc + t);
+    let mut summary = format!(
+        "Time: {}\nSegments: {}",
+        time.hhmmssxxx(),
+        result.segment_times.len()
     );
+    if result.personal_best {
+        summary.push_str("\nNew personal best!");
+    }
+    if result.best_segments > 0 {
+        summary.push_str(&format!("\nBest segments: {}", result.best_segments));
+    }
+    summary
+}
+
+/// Runs a stopwatch which tracks time for a project, and saves the session when it stops
+pub fn track(project: String, tags: Vec<String>) {
+    let mut siv = cursive::default();
+    let mut title = project.clone();
+    for tag in &tags {
+        title.push_str(&format!(" +{}", tag));
+    }
+    let start = Local::now();
+    let stopwatch =
+        StopwatchView::new()
+            .with_laps(8)
+            .running()
+            .on_stop(move |s: &mut Cursive, record| {
+                let session =
+                    Session::new(project.clone(), tags.clone(), start, Local::now(), &record);
+                let saved = SessionStore::open().and_then(|mut store| store.add(session));
+                let msg = match saved {
+                    Ok(id) => format!("{}\n\nSaved as session {}", summarize(&record, None), id),
+                    Err(e) => format!(
+                        "{}\n\nFailed to save the session: {}",
+                        summarize(&record, None),
+                        e
+                    ),
+                };
+                s.add_layer(Dialog::text(msg).button("Quit", |s| s.quit()));
+            });
+    siv.add_layer(Panel::new(stopwatch.with_name("stopwatch")).title(title));
     siv.set_fps(15);
     siv.run();
 }
 
-fn summarize(stopwatch: &StopwatchData) -> String {
+/// Shows reports of the given sessions, with a list to switch between the groupings
+pub fn report(sessions: Vec<Session>, group_by: GroupBy) {
+    let mut siv = cursive::default();
+    let mut select = SelectView::new();
+    for g in GroupBy::ALL.iter() {
+        select.add_item(format!("by {}", g.name()), *g);
+    }
+    let selected = GroupBy::ALL.iter().position(|&g| g == group_by).unwrap();
+    let table = TextView::new(Report::new(&sessions, group_by).to_table(40))
+        .with_name("report")
+        .scrollable();
+    let select = select
+        .selected(selected)
+        .on_select(move |s: &mut Cursive, &group_by| {
+            let table = Report::new(&sessions, group_by).to_table(40);
+            s.call_on_name("report", |v: &mut TextView| v.set_content(table));
+        });
+    siv.add_layer(
+        Dialog::around(
+            LinearLayout::horizontal()
+                .child(Panel::new(select))
+                .child(Panel::new(table)),
+        )
+        .title("Report")
+        .button("Quit", |s| s.quit()),
+    );
+    siv.run();
+}
+
+fn summarize(record: &StopwatchRecord, target_lap: Option<Duration>) -> String {
+    let stopwatch = &record.data;
     let elapsed = stopwatch.elapsed;
     let average = stopwatch.elapsed / stopwatch.laps.len() as i32;
     let max = stopwatch.laps.iter().max().unwrap();
     let min = stopwatch.laps.iter().min().unwrap();
-    format!(
+    let mut summary = format!(
         "Elapsed time: {}\nAverage: {}\nMax: {}\nMin: {}",
         elapsed.hhmmssxxx(),
         average.hhmmssxxx(),
         max.hhmmssxxx(),
         min.hhmmssxxx()
-    )
+    );
+    if let Some(target) = target_lap {
+        let over = stopwatch.laps.iter().filter(|&&lap| lap > target).count();
+        summary.push_str(&format!(
+            "\nOver target: {}\nUnder target: {}",
+            over,
+            stopwatch.laps.len() - over
+        ));
+    }
+    if record.lap_labels.iter().any(Option::is_some) {
+        summary.push('\n');
+        for (i, lap) in stopwatch.laps.iter().enumerate() {
+            summary.push_str(&format!(
+                "\nLap {:02}: {} {}",
+                i + 1,
+                lap.hhmmssxxx(),
+                record.lap_label(i).unwrap_or("")
+            ));
+        }
+    }
+    summary
 }
 
-fn timer_on_finish(data: TimerData) {
-    let expected_duration = data.duration_expected().hhmmss();
-    let actual_duration = data.duration_actual().hhmmss();
+fn timer_on_finish(data: TimerData, cancelled: bool) {
+    let _ = TimerRun::new(&data, !cancelled).record();
+
+    let expected_duration = dhhmmss(data.duration_expected());
+    let actual_duration = dhhmmss(data.duration_actual());
     let msg = &format!(
         "Expected: {}\nActual: {}",
         &expected_duration, &actual_duration,
     );
 
-    notify(msg).unwrap();
-
-    match notify(msg) {
-        Ok(_) => {}
-        Err(_) => {}
-    }
+    // e.g. no notification server is running
+    let _ = notify(msg);
 }
 
 #[allow(dead_code)]
@@ -72,11 +245,275 @@ fn timer_on_finish_debug(s: &mut Cursive, data: TimerData) {
     s.add_layer(Dialog::info(format!("{:?}", data)));
 }
 
-pub fn timer(h: u8, m: u8, s: u8) {
+/// Runs a countdown timer set up with the `TimerView` builders. When the remaining time reaches
+/// each of its warnings, the terminal bell rings and a notification is sent. Each run is recorded.
+pub fn timer(timer: TimerView) {
     let mut siv = cursive::default();
-    let timer = TimerView::new(h, m, s);
-    siv.add_layer(timer.on_finish(|_: &mut Cursive, timer| timer_on_finish(timer)));
+    let timer = timer
+        .on_warning(|_: &mut Cursive, threshold| {
+            bell();
+            let _ = notify(&format!("{} left", threshold));
+        })
+        .on_finish(|_: &mut Cursive, timer, cancelled| timer_on_finish(timer, cancelled));
+    siv.add_layer(timer);
     //siv.set_fps(15);
     siv.set_autorefresh(true);
     siv.run();
 }
+
+/// Shows countdowns to events, and sends a notification when one is reached. If `persist` is set,
+/// events removed in the view are also removed from the `EventStore`.
+pub fn countdown(events: Vec<CountdownEvent>, persist: bool) {
+    let mut siv = cursive::default();
+    let view = CountdownView::new(events)
+        .on_reached(|_: &mut Cursive, event| {
+            let _ = notify(&format!(
+                "{} ({})",
+                event.name,
+                event.target.format("%Y-%m-%d %H:%M")
+            ));
+        })
+        .on_remove(move |s: &mut Cursive, event| {
+            if persist {
+                if let Err(e) = EventStore::open().and_then(|mut store| store.remove(&event.name)) {
+                    s.add_layer(Dialog::info(format!(
+                        "Failed to remove {}: {}",
+                        event.name, e
+                    )));
+                }
+            }
+        });
+    siv.add_layer(
+        Dialog::around(view)
+            .title("Countdown")
+            .button("Quit", |s| s.quit()),
+    );
+    siv.set_fps(4);
+    siv.run();
+}
+
+/// Shows the recurring alarms in the config file, which are saved whenever one is enabled,
+/// disabled, snoozed (for `snooze`) or removed
+pub fn alarms(alarms: Vec<Alarm>, snooze: Duration) {
+    let mut siv = cursive::default();
+    let view = AlarmView::new(alarms)
+        .with_snooze(snooze)
+        .on_change(|s: &mut Cursive, alarms| {
+            let saved = Config::open().and_then(|mut config| {
+                config.alarms = alarms;
+                config.save()
+            });
+            if let Err(e) = saved {
+                s.add_layer(Dialog::info(format!("Failed to save the alarms: {}", e)));
+            }
+        });
+    siv.add_layer(
+        Dialog::around(view)
+            .title("Alarms")
+            .button("Quit", |s| s.quit()),
+    );
+    siv.set_fps(1);
+    siv.run();
+}
+
+/// Shows the live state of the timers, stopwatches and alarms owned by the daemon
+pub fn attach() {
+    let mut siv = cursive::default();
+    siv.add_layer(
+        Dialog::around(DaemonView::new())
+            .title("Daemon")
+            .button("Quit", |s| s.quit()),
+    );
+    siv.set_fps(4);
+    siv.run();
+}
+
+/// Shows the local time and the time in each of `zones`
+pub fn world(zones: Vec<Tz>) {
+    let mut siv = cursive::default();
+    siv.add_layer(
+        Dialog::around(WorldClockView::new(zones))
+            .title("World Clock")
+            .button("Quit", |s| s.quit()),
+    );
+    siv.set_fps(1);
+    siv.run();
+}
+
+/// Plans a meeting of `duration` across `zones`. The chosen slot is printed in every zone, and
+/// also copied to the clipboard if it was chosen with "y".
+pub fn plan(zones: Vec<Tz>, hours: WorkingHours, duration: Duration, date: Option<NaiveDate>) {
+    let mut siv = cursive::default();
+    let chosen = Rc::new(Cell::new(None));
+    let (selected, copied) = (chosen.clone(), chosen.clone());
+    let mut view = PlannerView::new(zones.clone())
+        .with_working_hours(hours)
+        .with_duration(duration)
+        .on_select(move |s: &mut Cursive, start| {
+            selected.set(Some((start, false)));
+            s.quit();
+        })
+        .on_copy(move |s: &mut Cursive, start| {
+            copied.set(Some((start, true)));
+            s.quit();
+        });
+    if let Some(date) = date {
+        view = view.with_date(date);
+    }
+    siv.add_layer(
+        Dialog::around(view)
+            .title("Meeting Planner")
+            .button("Quit", |s| s.quit()),
+    );
+    siv.run();
+    drop(siv);
+    if let Some((start, copy)) = chosen.get() {
+        let slot = describe_slot(start, duration, &zones);
+        println!("{}", slot);
+        if copy {
+            copy_to_clipboard(&slot);
+        }
+    }
+}
+
+/// Copies `text` to the clipboard with the OSC 52 escape sequence, which most terminal emulators
+/// support without any clipboard tool
+fn copy_to_clipboard(text: &str) {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in text.as_bytes().chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    print!("\x1b]52;c;{}\x07", encoded);
+    let _ = std::io::stdout().flush();
+}
+
+/// Shows the current time in large digits on the whole screen, until "q" or "Esc" is pressed. The
+/// time in `zones` (if any) is shown below it, as a world clock.
+pub fn now(view: WallClockView, zones: Vec<Tz>) {
+    let mut siv = cursive::default();
+    let mut layout = LinearLayout::vertical().child(view.full_screen());
+    if !zones.is_empty() {
+        // the wall clock already shows the local time
+        layout.add_child(
+            LinearLayout::horizontal()
+                .child(DummyView.full_width())
+                .child(WorldClockView::new(zones).without_local())
+                .child(DummyView.full_width()),
+        );
+    }
+    siv.add_fullscreen_layer(layout);
+    siv.add_global_callback('q', |s| s.quit());
+    siv.add_global_callback(Key::Esc, |s| s.quit());
+    siv.set_fps(4);
+    siv.run();
+}
+
+/// Runs a game clock, and writes the moves to `export` (if any) when the game is ended
+pub fn game(game: Game, export: Option<PathBuf>) {
+    let mut siv = cursive::default();
+    let view = GameClockView::new(game)
+        .on_flag(|_: &mut Cursive, _| bell())
+        .on_finish(move |s: &mut Cursive, game| {
+            let mut msg = summarize_game(&game);
+            if let Some(path) = &export {
+                match game.export(path) {
+                    Ok(()) => msg.push_str(&format!("\n\nMoves written to {}", path.display())),
+                    Err(e) => {
+                        msg.push_str(&format!("\n\nFailed to write {}: {}", path.display(), e))
+                    }
+                }
+            }
+            s.add_layer(Dialog::text(msg).button("Quit", |s| s.quit()));
+        });
+    siv.add_layer(view);
+    siv.set_fps(15);
+    siv.run();
+}
+
+fn summarize_game(game: &Game) -> String {
+    let mut summary = String::new();
+    for (i, player) in game.players.iter().enumerate() {
+        let times = game.moves_of(i).map(|m| m.time).collect::<Vec<_>>();
+        let total = times.iter().fold(Duration::zero(), |acc, &t| acc + t);
+        let average = if times.is_empty() {
+            Duration::zero()
+        } else {
+            total / times.len() as i32
+        };
+        summary.push_str(&format!(
+            "{}: {} moves, {} on average, {} left{}\n",
+            player.name,
+            times.len(),
+            average.hhmmssxxx(),
+            player.remaining.hhmmssxxx(),
+            if player.flagged { " (flag fell)" } else { "" }
+        ));
+    }
+    if let Some(winner) = game.winner() {
+        summary.push_str(&format!("\n{} wins on time", winner.name));
+    }
+    summary.trim_end().to_owned()
+}
+
+/// Runs a metronome which rings the terminal bell (if `bell`) and plays `sound` on every beat,
+/// or `accent_sound` (if any) on accented beats
+pub fn metronome(
+    view: MetronomeView,
+    bell_on_beat: bool,
+    sound: Option<PathBuf>,
+    accent_sound: Option<PathBuf>,
+) {
+    let mut siv = cursive::default();
+    let view = view.on_beat(move |_: &mut Cursive, beat| {
+        if bell_on_beat {
+            bell();
+        }
+        let sound = match beat {
+            Beat::Accent => accent_sound.as_ref().or(sound.as_ref()),
+            _ => sound.as_ref(),
+        };
+        if let Some(sound) = sound {
+            play(sound);
+        }
+    });
+    siv.add_layer(
+        Dialog::around(view)
+            .title("Metronome")
+            .button("Quit", |s| s.quit()),
+    );
+    // the beats are checked for on every refresh
+    siv.set_fps(60);
+    siv.run();
+}
+
+/// Runs a timer for each speaker in turn, and prints how long each of them spoke
+pub fn standup(speakers: Vec<Speaker>) {
+    let mut siv = cursive::default();
+    siv.add_layer(
+        Dialog::around(
+            StandupView::new(speakers)
+                .on_finish(|s: &mut Cursive, _| s.quit())
+                .with_name("standup"),
+        )
+        .title("Standup")
+        .button("Quit", |s| s.quit()),
+    );
+    siv.set_fps(15);
+    siv.run();
+    let speakers = siv.call_on_name("standup", |v: &mut StandupView| v.speakers());
+    drop(siv);
+    if let Some(speakers) = speakers {
+        print!("{}", crate::standup::report(&speakers));
+    }
+}
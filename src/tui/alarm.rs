@@ -0,0 +1,212 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Alarm Management TUI
+//!
+//! ## Expected Behavior
+//!
+//! Lists the recurring alarms with their schedules and when each rings next. Up/down arrow keys
+//! move the selection, "Space" enables or disables the selected alarm, "z" snoozes it (see
+//! `Alarm::snooze`), and "x" or "Delete" removes it.
+//!
+//! After each change, the callback set with `on_change()` is called with all the alarms.
+
+use crate::alarm::Alarm;
+use chrono::{Duration, Local};
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    theme::ColorStyle,
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use std::rc::Rc;
+
+/// Called with the alarms whenever they are changed
+type OnChange = Rc<dyn Fn(&mut Cursive, Vec<Alarm>)>;
+
+pub struct AlarmView {
+    alarms: Vec<Alarm>,
+    selected: usize,
+    snooze: Duration,
+    on_change: Option<OnChange>,
+}
+
+impl AlarmView {
+    pub fn new(alarms: Vec<Alarm>) -> Self {
+        Self {
+            alarms,
+            selected: 0,
+            snooze: Duration::minutes(5),
+            on_change: None,
+        }
+    }
+
+    /// Sets how long "z" snoozes an alarm for. Defaults to 5 minutes.
+    pub fn with_snooze(mut self, snooze: Duration) -> Self {
+        self.snooze = snooze;
+        self
+    }
+
+    /// Sets a callback to be used when an alarm is enabled, disabled, snoozed or removed
+    pub fn set_on_change<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Vec<Alarm>) -> R,
+    {
+        self.on_change = Some(Rc::new(move |s, alarms| {
+            cb(s, alarms);
+        }));
+    }
+
+    pub fn on_change<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Vec<Alarm>) -> R,
+    {
+        self.with(|s| s.set_on_change(cb))
+    }
+
+    fn changed(&self) -> EventResult {
+        match self.on_change.clone() {
+            Some(cb) => {
+                let alarms = self.alarms.clone();
+                EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, alarms))))
+            }
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.alarms
+            .iter()
+            .map(|a| a.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8)
+    }
+
+    fn schedule_width(&self) -> usize {
+        self.alarms
+            .iter()
+            .map(|a| a.schedule.to_string().chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(14)
+    }
+
+    fn status(alarm: &Alarm) -> String {
+        let now = Local::now();
+        match alarm.next_ring(now) {
+            None if !alarm.enabled => "disabled".to_owned(),
+            None => "never".to_owned(),
+            Some(ring) => {
+                let when = if ring.date_naive() == now.date_naive() {
+                    ring.format("%H:%M").to_string()
+                } else {
+                    ring.format("%a %d %b %H:%M").to_string()
+                };
+                match alarm.snoozed_until {
+                    Some(snoozed_until) if snoozed_until == ring => format!("snoozed to {}", when),
+                    _ => when,
+                }
+            }
+        }
+    }
+}
+
+impl View for AlarmView {
+    fn draw(&self, printer: &Printer) {
+        if self.alarms.is_empty() {
+            printer.print((0, 0), "No alarms. Add one with `clock alarm add`.");
+            return;
+        }
+        let name_width = self.name_width();
+        let schedule_width = self.schedule_width();
+        printer.with_color(ColorStyle::secondary(), |printer| {
+            printer.print(
+                (0, 0),
+                &format!(
+                    "    {:<nw$}  {:<sw$}  Next",
+                    "Alarm",
+                    "Schedule",
+                    nw = name_width,
+                    sw = schedule_width
+                ),
+            )
+        });
+        for (i, alarm) in self.alarms.iter().enumerate() {
+            let line = format!(
+                "[{}] {:<nw$}  {:<sw$}  {}",
+                if alarm.enabled { "x" } else { " " },
+                alarm.name,
+                alarm.schedule.to_string(),
+                Self::status(alarm),
+                nw = name_width,
+                sw = schedule_width
+            );
+            let style = if i == self.selected {
+                ColorStyle::highlight()
+            } else if alarm.enabled {
+                ColorStyle::primary()
+            } else {
+                ColorStyle::secondary()
+            };
+            printer.with_color(style, |printer| printer.print((0, i + 1), &line));
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        Vec2::new(
+            std::cmp::max(self.name_width() + self.schedule_width() + 35, 42),
+            self.alarms.len() + 1,
+        ) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        if self.alarms.is_empty() {
+            return EventResult::Ignored;
+        }
+        let alarm = &mut self.alarms[self.selected];
+        match event {
+            Event::Key(Key::Up) => self.selected = self.selected.saturating_sub(1),
+            Event::Key(Key::Down) => {
+                if self.selected + 1 < self.alarms.len() {
+                    self.selected += 1;
+                }
+            }
+            Event::Char(' ') => {
+                alarm.enabled = !alarm.enabled;
+                alarm.snoozed_until = None;
+                return self.changed();
+            }
+            Event::Char('z') => {
+                alarm.snooze(self.snooze, Local::now());
+                return self.changed();
+            }
+            Event::Char('x') | Event::Key(Key::Del) => {
+                self.alarms.remove(self.selected);
+                self.selected = self.selected.min(self.alarms.len().saturating_sub(1));
+                return self.changed();
+            }
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Analog Clock TUI
+//!
+//! ## Expected Behavior
+//!
+//! Draws a round face with braille dots (2 columns and 4 rows of dots per character, so that the
+//! dots are about as wide as they are high), scaled to fill the available area. By default, the
+//! current local time is shown with the hour, minute and (red) second hands.
+//!
+//! As a countdown ring (`AnalogClockView::ring()`), an arc starts at 12 o'clock and goes
+//! clockwise for the fraction of the time left given to `set_remaining()`, so it shrinks as the
+//! time runs out. The arc is drawn in the color of the printer, e.g. the color of a timer.
+
+use chrono::{Local, Timelike};
+use cursive::{
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
+    view::View,
+    Printer, Vec2,
+};
+use std::f64::consts::PI;
+
+/// The bits of the braille dots in a character, by row and column
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Braille dots, with the origin at the top-left dot
+struct Canvas {
+    /// Columns and rows of characters
+    size: Vec2,
+    cells: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            cells: vec![0; size.x * size.y],
+        }
+    }
+
+    fn set(&mut self, x: f64, y: f64) {
+        let (x, y) = (x.round(), y.round());
+        if x < 0.0 || y < 0.0 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.size.x * 2 || y >= self.size.y * 4 {
+            return;
+        }
+        self.cells[y / 4 * self.size.x + x / 2] |= DOTS[y % 4][x % 2];
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64)) {
+        let steps = ((to.0 - from.0).abs().max((to.1 - from.1).abs()) * 2.0).ceil() as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps.max(1) as f64;
+            self.set(from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+        }
+    }
+}
+
+/// A canvas and its color, or `None` for the color of the printer
+type Layer = (Canvas, Option<ColorStyle>);
+
+/// Draws the canvases on top of each other: each character has the dots of all of them, in the
+/// color of the last one with a dot there
+fn draw_layers(printer: &Printer, layers: &[Layer]) {
+    let size = match layers.first() {
+        Some((canvas, _)) => canvas.size,
+        None => return,
+    };
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let i = y * size.x + x;
+            let bits = layers.iter().fold(0, |bits, (c, _)| bits | c.cells[i]);
+            if bits == 0 {
+                continue;
+            }
+            let dot = std::char::from_u32(0x2800 + bits as u32)
+                .unwrap()
+                .to_string();
+            match layers.iter().rev().find(|(c, _)| c.cells[i] != 0) {
+                Some((_, Some(style))) => {
+                    printer.with_color(*style, |printer| printer.print((x, y), &dot))
+                }
+                _ => printer.print((x, y), &dot),
+            }
+        }
+    }
+}
+
+pub struct AnalogClockView {
+    /// The fraction of the time left shown as an arc, or `None` to show the current time
+    remaining: Option<f64>,
+    seconds: bool,
+}
+
+impl Default for AnalogClockView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalogClockView {
+    /// A clock showing the current local time, with a second hand
+    pub fn new() -> Self {
+        Self {
+            remaining: None,
+            seconds: true,
+        }
+    }
+
+    /// A countdown ring with `remaining` (from 0 to 1) of the time left
+    pub fn ring(remaining: f64) -> Self {
+        Self {
+            remaining: Some(remaining.clamp(0.0, 1.0)),
+            seconds: false,
+        }
+    }
+
+    /// Sets the fraction of the time left, turning the clock into a countdown ring
+    pub fn set_remaining(&mut self, remaining: f64) {
+        self.remaining = Some(remaining.clamp(0.0, 1.0));
+    }
+
+    pub fn without_seconds(mut self) -> Self {
+        self.seconds = false;
+        self
+    }
+
+    /// Shows or hides the second hand
+    pub fn set_seconds(&mut self, seconds: bool) {
+        self.seconds = seconds;
+    }
+
+    /// The hour marks, and the minute marks if there is room for them
+    fn draw_marks(canvas: &mut Canvas, center: (f64, f64), radius: f64) {
+        let point = |angle: f64, length: f64| {
+            (
+                center.0 + length * angle.sin(),
+                center.1 - length * angle.cos(),
+            )
+        };
+        for i in 0..60 {
+            let angle = i as f64 * PI / 30.0;
+            if i % 5 == 0 {
+                let inner = if i % 15 == 0 { 0.8 } else { 0.88 };
+                canvas.line(point(angle, radius * inner), point(angle, radius));
+            } else if radius >= 12.0 {
+                let (x, y) = point(angle, radius);
+                canvas.set(x, y);
+            }
+        }
+    }
+
+    /// The arc of the time left, over a thin circle
+    fn draw_ring(&self, remaining: f64, size: Vec2, center: (f64, f64), radius: f64) -> Vec<Layer> {
+        let mut track = Canvas::new(size);
+        let mut arc = Canvas::new(size);
+        let outer = radius;
+        let inner = (radius * 0.8).min(radius - 1.5);
+        let middle = (outer + inner) / 2.0;
+        let end = remaining * 2.0 * PI;
+        for y in 0..size.y * 4 {
+            for x in 0..size.x * 2 {
+                let (dx, dy) = (x as f64 - center.0, y as f64 - center.1);
+                let distance = dx.hypot(dy);
+                // clockwise from 12 o'clock
+                let mut angle = dx.atan2(-dy);
+                if angle < 0.0 {
+                    angle += 2.0 * PI;
+                }
+                if angle < end && distance >= inner && distance <= outer {
+                    arc.set(x as f64, y as f64);
+                } else if (distance - middle).abs() < 0.5 {
+                    track.set(x as f64, y as f64);
+                }
+            }
+        }
+        vec![(track, Some(ColorStyle::secondary())), (arc, None)]
+    }
+
+    /// The marks, and the hands at the current time
+    fn draw_face(&self, size: Vec2, center: (f64, f64), radius: f64) -> Vec<Layer> {
+        let point = |angle: f64, length: f64| {
+            (
+                center.0 + radius * length * angle.sin(),
+                center.1 - radius * length * angle.cos(),
+            )
+        };
+        let now = Local::now().time();
+        let seconds = now.num_seconds_from_midnight() as f64 + now.nanosecond() as f64 / 1e9;
+
+        let mut marks = Canvas::new(size);
+        Self::draw_marks(&mut marks, center, radius);
+        let mut hands = Canvas::new(size);
+        hands.line(center, point(seconds / 43200.0 * 2.0 * PI, 0.5));
+        hands.line(center, point(seconds % 3600.0 / 3600.0 * 2.0 * PI, 0.8));
+        let mut second_hand = Canvas::new(size);
+        if self.seconds {
+            second_hand.line(center, point(seconds.floor() % 60.0 / 60.0 * 2.0 * PI, 0.9));
+        }
+        let red = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        vec![
+            (marks, Some(ColorStyle::secondary())),
+            (hands, None),
+            (second_hand, Some(red)),
+        ]
+    }
+}
+
+impl View for AnalogClockView {
+    fn draw(&self, printer: &Printer) {
+        let size = printer.size;
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+        // the face is centered, in dots
+        let center = (size.x as f64 - 0.5, size.y as f64 * 2.0 - 0.5);
+        let radius = center.0.min(center.1) - 0.5;
+
+        let layers = match self.remaining {
+            Some(remaining) => self.draw_ring(remaining, size, center, radius),
+            None => self.draw_face(size, center, radius),
+        };
+        draw_layers(printer, &layers);
+    }
+
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        // as large as possible while staying round, i.e. twice as many columns as rows
+        let rows = constraint.y.min(constraint.x / 2).max(5);
+        Vec2::new(rows * 2, rows) // columns, rows (width, height)
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A row of clickable buttons, drawn as `<Start> <Cancel>` like cursive's `Button`. The views draw
+//! the row themselves and use `button_at` to find out which button a mouse click hit.
+
+use cursive::Printer;
+
+/// Draws the buttons on the `row`th row, separated by a space
+pub(crate) fn draw_buttons(printer: &Printer, row: usize, labels: &[&str]) {
+    let mut x = 0;
+    for label in labels {
+        let button = format!("<{}>", label);
+        printer.print((x, row), &button);
+        x += button.chars().count() + 1;
+    }
+}
+
+/// The index of the button at column `x`
+pub(crate) fn button_at(labels: &[&str], x: usize) -> Option<usize> {
+    let mut start = 0;
+    for (i, label) in labels.iter().enumerate() {
+        let end = start + label.chars().count() + 2;
+        if (start..end).contains(&x) {
+            return Some(i);
+        }
+        start = end + 1;
+    }
+    None
+}
+
+/// The width of the row of buttons
+pub(crate) fn buttons_width(labels: &[&str]) -> usize {
+    labels
+        .iter()
+        .map(|label| label.chars().count() + 3)
+        .sum::<usize>()
+        - 1
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Countdown-to-Event TUI
+//!
+//! ## Expected Behavior
+//!
+//! Lists events with their target date and the time left in days, hours, minutes and seconds. The
+//! selected event is also shown above the list, one component per column. Up/down arrow keys move
+//! the selection, and "x" or "Delete" removes the selected event.
+//!
+//! Events which have passed are shown dimmed, with the time since they passed. When an event is
+//! reached while the view is shown, the callback set with `on_reached()` is called.
+
+use crate::countdown::CountdownEvent;
+use crate::utils::dhhmmss;
+use chrono::{DateTime, Duration, Local};
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    theme::{ColorStyle, Effect},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use std::rc::Rc;
+
+/// Called with an event when it is reached or removed
+type EventCallback = Rc<dyn Fn(&mut Cursive, CountdownEvent)>;
+
+pub struct CountdownView {
+    events: Vec<CountdownEvent>,
+    /// Whether each event had been reached the last time the view was refreshed
+    reached: Vec<bool>,
+    selected: usize,
+    on_reached: Option<EventCallback>,
+    on_remove: Option<EventCallback>,
+}
+
+impl CountdownView {
+    pub fn new(events: Vec<CountdownEvent>) -> Self {
+        let now = Local::now();
+        let reached = events
+            .iter()
+            .map(|e| e.remaining(now) <= Duration::zero())
+            .collect();
+        Self {
+            events,
+            reached,
+            selected: 0,
+            on_reached: None,
+            on_remove: None,
+        }
+    }
+
+    /// Sets a callback to be used when the target of an event is reached
+    pub fn set_on_reached<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, CountdownEvent) -> R,
+    {
+        self.on_reached = Some(Rc::new(move |s, e| {
+            cb(s, e);
+        }));
+    }
+
+    pub fn on_reached<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, CountdownEvent) -> R,
+    {
+        self.with(|s| s.set_on_reached(cb))
+    }
+
+    /// Sets a callback to be used when an event is removed with "x" or "Delete"
+    pub fn set_on_remove<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, CountdownEvent) -> R,
+    {
+        self.on_remove = Some(Rc::new(move |s, e| {
+            cb(s, e);
+        }));
+    }
+
+    pub fn on_remove<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, CountdownEvent) -> R,
+    {
+        self.with(|s| s.set_on_remove(cb))
+    }
+
+    fn remove_selected(&mut self) -> EventResult {
+        if self.events.is_empty() {
+            return EventResult::Consumed(None);
+        }
+        let event = self.events.remove(self.selected);
+        self.reached.remove(self.selected);
+        self.selected = self.selected.min(self.events.len().saturating_sub(1));
+        match self.on_remove.clone() {
+            Some(cb) => EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, event)))),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    fn check_reached(&mut self) -> EventResult {
+        let now = Local::now();
+        let mut newly_reached = Vec::new();
+        for (event, reached) in self.events.iter().zip(self.reached.iter_mut()) {
+            if !*reached && event.remaining(now) <= Duration::zero() {
+                *reached = true;
+                newly_reached.push(event.clone());
+            }
+        }
+        match self.on_reached.clone() {
+            Some(cb) if !newly_reached.is_empty() => {
+                EventResult::Consumed(Some(Callback::from_fn_once(move |s| {
+                    for event in newly_reached {
+                        cb(s, event);
+                    }
+                })))
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.events
+            .iter()
+            .map(|e| e.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8)
+    }
+
+    fn draw_selected(&self, printer: &Printer, event: &CountdownEvent, now: DateTime<Local>) {
+        let remaining = event.remaining(now);
+        let s = remaining.num_seconds().abs();
+        let components = [
+            (s / 86400, "days"),
+            (s % 86400 / 3600, "hours"),
+            (s % 3600 / 60, "minutes"),
+            (s % 60, "seconds"),
+        ];
+        for (i, (n, unit)) in components.iter().enumerate() {
+            let x = i * 10;
+            printer.with_effect(Effect::Bold, |printer| {
+                printer.print((x, 0), &format!("{:>7}", n))
+            });
+            printer.print((x, 1), &format!("{:>7}", unit));
+        }
+        let caption = if remaining > Duration::zero() {
+            format!("until {}", event.name)
+        } else {
+            format!("since {}", event.name)
+        };
+        printer.print((0, 2), &caption);
+    }
+}
+
+impl View for CountdownView {
+    fn draw(&self, printer: &Printer) {
+        if self.events.is_empty() {
+            printer.print((0, 0), "No events. Add one with `clock countdown add`.");
+            return;
+        }
+        let now = Local::now();
+        self.draw_selected(printer, &self.events[self.selected], now);
+
+        let name_width = self.name_width();
+        for (i, event) in self.events.iter().enumerate() {
+            let row = i + 4;
+            let remaining = event.remaining(now);
+            let line = format!(
+                "{:<width$}  {}  {:>16}{}",
+                event.name,
+                event.target.format("%Y-%m-%d %a %H:%M"),
+                dhhmmss(remaining),
+                if remaining > Duration::zero() {
+                    ""
+                } else {
+                    " ago"
+                },
+                width = name_width
+            );
+            let style = if i == self.selected {
+                ColorStyle::highlight()
+            } else if remaining > Duration::zero() {
+                ColorStyle::primary()
+            } else {
+                ColorStyle::secondary()
+            };
+            printer.with_color(style, |printer| printer.print((0, row), &line));
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        Vec2::new(
+            std::cmp::max(self.name_width() + 46, 47),
+            self.events.len() + 4,
+        ) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Refresh => return self.check_reached(),
+            Event::Key(Key::Up) => self.selected = self.selected.saturating_sub(1),
+            Event::Key(Key::Down) => {
+                if self.selected + 1 < self.events.len() {
+                    self.selected += 1;
+                }
+            }
+            Event::Char('x') | Event::Key(Key::Del) => return self.remove_selected(),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
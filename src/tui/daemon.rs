@@ -0,0 +1,173 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Daemon Client TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows the live state of the timers and stopwatches owned by the daemon, and when the enabled
+//! alarms ring next. Up/down arrow keys move the selection, "Space" pauses/resumes the selected
+//! timer or stopwatch, "l" laps the selected stopwatch, and "x", "Delete" or "Enter" stops it.
+
+use crate::daemon::{request, Job, Request, Response, Status};
+use chrono::Local;
+use cursive::{
+    direction::Direction,
+    event::{Event, EventResult, Key},
+    theme::ColorStyle,
+    view::View,
+    Printer, Vec2,
+};
+
+pub struct DaemonView {
+    status: Result<Status, String>,
+    selected: usize,
+    /// The result of the last key press, e.g. an error from the daemon
+    message: Option<String>,
+}
+
+impl Default for DaemonView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DaemonView {
+    pub fn new() -> Self {
+        let mut view = Self {
+            status: Err(String::new()),
+            selected: 0,
+            message: None,
+        };
+        view.refresh();
+        view
+    }
+
+    fn refresh(&mut self) {
+        self.status = match request(&Request::Status) {
+            Ok(Response::Status(status)) => Ok(status),
+            Ok(response) => Err(format!("unexpected response: {:?}", response)),
+            Err(e) => Err(e.to_string()),
+        };
+        let jobs = self.jobs().len();
+        self.selected = self.selected.min(jobs.saturating_sub(1));
+    }
+
+    fn jobs(&self) -> &[Job] {
+        match &self.status {
+            Ok(status) => &status.jobs,
+            Err(_) => &[],
+        }
+    }
+
+    fn send(&mut self, request_for: fn(u32) -> Request) {
+        let id = match self.jobs().get(self.selected) {
+            Some(job) => job.id(),
+            None => return,
+        };
+        self.message = match request(&request_for(id)) {
+            Ok(Response::Stopped { job }) => Some(format!("Stopped: {}", job)),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        self.refresh();
+    }
+
+    fn lines(&self) -> Vec<(String, ColorStyle)> {
+        let status = match &self.status {
+            Ok(status) => status,
+            Err(e) => return vec![(e.clone(), ColorStyle::secondary())],
+        };
+        let mut lines = Vec::new();
+        if status.jobs.is_empty() {
+            lines.push((
+                "No timers or stopwatches. Start one with `clock 20m --detach`.".to_owned(),
+                ColorStyle::secondary(),
+            ));
+        }
+        for (i, job) in status.jobs.iter().enumerate() {
+            let style = if i == self.selected {
+                ColorStyle::highlight()
+            } else {
+                ColorStyle::primary()
+            };
+            lines.push((job.to_string(), style));
+        }
+        let now = Local::now();
+        let alarms: Vec<_> = status
+            .alarms
+            .iter()
+            .filter_map(|a| a.next_ring(now).map(|ring| (a, ring)))
+            .collect();
+        if !alarms.is_empty() {
+            lines.push((String::new(), ColorStyle::primary()));
+            lines.push(("Alarms".to_owned(), ColorStyle::secondary()));
+        }
+        for (alarm, ring) in alarms {
+            lines.push((
+                format!("{}  {}", ring.format("%a %H:%M"), alarm.name),
+                ColorStyle::primary(),
+            ));
+        }
+        if let Some(message) = &self.message {
+            lines.push((String::new(), ColorStyle::primary()));
+            lines.push((message.clone(), ColorStyle::secondary()));
+        }
+        lines
+    }
+}
+
+impl View for DaemonView {
+    fn draw(&self, printer: &Printer) {
+        for (row, (line, style)) in self.lines().iter().enumerate() {
+            printer.with_color(*style, |printer| printer.print((0, row), line));
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        let lines = self.lines();
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        Vec2::new(width.max(48), lines.len()) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Refresh => self.refresh(),
+            Event::Key(Key::Up) => self.selected = self.selected.saturating_sub(1),
+            Event::Key(Key::Down) => {
+                if self.selected + 1 < self.jobs().len() {
+                    self.selected += 1;
+                }
+            }
+            Event::Char(' ') => self.send(|id| Request::PauseOrResume { id }),
+            Event::Char('l') => self.send(|id| Request::Lap { id }),
+            Event::Char('x') | Event::Key(Key::Del) | Event::Key(Key::Enter) => {
+                self.send(|id| Request::Stop { id })
+            }
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Game Clock TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows the countdown of each player, with the player whose turn it is highlighted. Press "Space"
+//! to start the clock, and then to end the current move and start the next player's countdown.
+//! Press "p" to pause/resume, and "Enter" to end the game.
+//!
+//! With a simple delay, the delay left before the countdown starts is shown next to it. When a
+//! flag falls, the player is marked with "flag" and the callback set with `on_flag()` is called.
+//! When the game is ended, the callback set with `on_finish()` is called with the game and its
+//! moves.
+
+use crate::game::Game;
+use chrono::Local;
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    theme::{BaseColor, Color, ColorStyle, Effect, PaletteColor},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use hhmmss::Hhmmss;
+use std::rc::Rc;
+
+/// Called with the name of the player whose flag falls
+type OnFlag = Rc<dyn Fn(&mut Cursive, String)>;
+
+/// Called with the game when it is ended
+type OnFinish = Rc<dyn Fn(&mut Cursive, Game)>;
+
+pub struct GameClockView {
+    game: Game,
+    on_flag: Option<OnFlag>,
+    on_finish: Option<OnFinish>,
+}
+
+impl GameClockView {
+    pub fn new(game: Game) -> Self {
+        Self {
+            game,
+            on_flag: None,
+            on_finish: None,
+        }
+    }
+
+    /// Sets a callback to be used when the flag of a player falls
+    ///
+    /// The name of the player will be given to the callback.
+    pub fn set_on_flag<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, String) -> R,
+    {
+        self.on_flag = Some(Rc::new(move |s, name| {
+            cb(s, name);
+        }));
+    }
+
+    pub fn on_flag<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, String) -> R,
+    {
+        self.with(|s| s.set_on_flag(cb))
+    }
+
+    /// Sets a callback to be used when the game is ended with "Enter"
+    pub fn set_on_finish<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Game) -> R,
+    {
+        self.on_finish = Some(Rc::new(move |s, game| {
+            cb(s, game);
+        }));
+    }
+
+    pub fn on_finish<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Game) -> R,
+    {
+        self.with(|s| s.set_on_finish(cb))
+    }
+
+    fn check_flag(&mut self) -> EventResult {
+        match self.game.check_flag(Local::now()) {
+            Some(player) => {
+                let name = self.game.players[player].name.clone();
+                match self.on_flag.clone() {
+                    Some(cb) => {
+                        EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, name))))
+                    }
+                    None => EventResult::Consumed(None),
+                }
+            }
+            None => EventResult::Ignored,
+        }
+    }
+
+    fn finish(&mut self) -> EventResult {
+        let now = Local::now();
+        if self.game.is_running() {
+            self.game.pause_or_resume(now);
+        }
+        let game = self.game.clone();
+        match self.on_finish.clone() {
+            Some(cb) => EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, game)))),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.game
+            .players
+            .iter()
+            .map(|p| p.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(6)
+    }
+
+    fn status(&self) -> String {
+        if let Some(winner) = self.game.winner() {
+            return format!("{} wins on time", winner.name);
+        }
+        let active = &self.game.players[self.game.active()].name;
+        if self.game.is_running() {
+            format!("Move {}: {}", self.game.moves.len() + 1, active)
+        } else if self.game.moves.is_empty() && self.game.move_time(Local::now()).is_zero() {
+            format!("Press Space to start {}'s clock", active)
+        } else {
+            "PAUSED".to_owned()
+        }
+    }
+}
+
+impl View for GameClockView {
+    fn draw(&self, printer: &Printer) {
+        let now = Local::now();
+        let flagged = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        printer.print((0, 0), &self.game.control.to_string());
+
+        let name_width = self.name_width();
+        for (i, player) in self.game.players.iter().enumerate() {
+            let row = i + 2;
+            let active = i == self.game.active() && !self.game.is_over();
+            let style = if active {
+                ColorStyle::highlight()
+            } else {
+                ColorStyle::primary()
+            };
+            printer.with_color(style, |printer| {
+                printer.print((0, row), &format!("{:<1$}", player.name, name_width))
+            });
+
+            let remaining = self.game.remaining(i, now).hhmmssxxx();
+            let x = name_width + 2;
+            if player.flagged {
+                printer.with_color(flagged, |printer| {
+                    printer.print((x, row), &format!("{}  flag", remaining))
+                });
+                continue;
+            }
+            if active {
+                printer.with_effect(Effect::Bold, |printer| printer.print((x, row), &remaining));
+            } else {
+                printer.print((x, row), &remaining);
+            }
+            let moves = self.game.moves_of(i).count();
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print((x + 14, row), &format!("{:>3} moves", moves));
+                if let Some(delay) = self.game.delay_left(now).filter(|d| active && !d.is_zero()) {
+                    printer.print((x + 25, row), &format!("delay {}", delay.hhmmssxxx()));
+                }
+            });
+        }
+
+        let footer = self.game.players.len() + 3;
+        printer.print((0, footer), &self.status());
+        if let Some(last) = self.game.moves.last() {
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print(
+                    (0, footer + 1),
+                    &format!(
+                        "Last move: {} in {}",
+                        self.game.players[last.player].name,
+                        last.time.hhmmssxxx()
+                    ),
+                )
+            });
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        let width = (self.name_width() + 46).max(self.game.control.to_string().len());
+        Vec2::new(width, self.game.players.len() + 5) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Refresh => return self.check_flag(),
+            // the flag may have fallen since the last refresh
+            Event::Char(' ') => match self.check_flag() {
+                EventResult::Ignored => self.game.switch(Local::now()),
+                flagged => return flagged,
+            },
+            Event::Char('p') => self.game.pause_or_resume(Local::now()),
+            Event::Key(Key::Enter) => return self.finish(),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Metronome TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows the tempo and a box for each beat of the pattern. On every beat, the box of the beat
+//! flashes (in red when the beat is accented) and the callback set with `on_beat()` is called,
+//! except on silent beats.
+//!
+//! Press "Space" to start/stop. Press "Up"/"Down" (or "+"/"-") to change the tempo by one beat
+//! per minute (or one second for an interval), and "PageUp"/"PageDown" by ten. Press "t" on the
+//! beat a few times to set the tempo to the average time between the taps.
+//!
+//! The beats are checked for on every refresh, so the refresh rate (e.g. `set_fps()`) limits how
+//! late a beat can be, but the lateness never adds up over the beats.
+
+use crate::metronome::{Beat, Pattern, Schedule, TapTempo, Tempo};
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    theme::{BaseColor, Color, ColorStyle, Effect, PaletteColor},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The columns and rows of the box of each beat
+const BOX_SIZE: (usize, usize) = (5, 3);
+/// How long a beat flashes for, at most
+const FLASH: Duration = Duration::from_millis(150);
+
+/// Called with each beat, when it is due
+type OnBeat = Rc<dyn Fn(&mut Cursive, Beat)>;
+
+pub struct MetronomeView {
+    tempo: Tempo,
+    pattern: Pattern,
+    /// `None` when the metronome is stopped
+    schedule: Option<Schedule>,
+    /// The number of the latest beat, and when it was noticed
+    last_beat: Option<(u64, Instant)>,
+    taps: TapTempo,
+    on_beat: Option<OnBeat>,
+}
+
+impl MetronomeView {
+    pub fn new(tempo: Tempo) -> Self {
+        Self {
+            tempo,
+            pattern: Pattern::default(),
+            schedule: None,
+            last_beat: None,
+            taps: TapTempo::default(),
+            on_beat: None,
+        }
+    }
+
+    /// Sets the accented, normal and silent beats, e.g. `X...`
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Starts the metronome right away
+    pub fn running(mut self) -> Self {
+        self.start_or_stop();
+        self
+    }
+
+    /// Sets a callback to be used on every beat which is not silent
+    pub fn set_on_beat<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Beat) -> R,
+    {
+        self.on_beat = Some(Rc::new(move |s, beat| {
+            cb(s, beat);
+        }));
+    }
+
+    pub fn on_beat<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Beat) -> R,
+    {
+        self.with(|s| s.set_on_beat(cb))
+    }
+
+    fn start_or_stop(&mut self) {
+        self.schedule = match self.schedule {
+            Some(_) => None,
+            None => Some(Schedule::new(Instant::now(), self.tempo.interval())),
+        };
+        self.last_beat = None;
+    }
+
+    fn set_tempo(&mut self, tempo: Tempo) {
+        self.tempo = tempo;
+        if let Some(schedule) = &mut self.schedule {
+            schedule.set_interval(tempo.interval());
+        }
+    }
+
+    fn check_beat(&mut self) -> EventResult {
+        let now = Instant::now();
+        let n = match self.schedule.as_mut().and_then(|s| s.poll(now)) {
+            Some(n) => n,
+            None => return EventResult::Ignored,
+        };
+        self.last_beat = Some((n, now));
+        let beat = self.pattern.beat(n);
+        match self.on_beat.clone() {
+            Some(cb) if beat != Beat::Rest => {
+                EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, beat))))
+            }
+            _ => EventResult::Consumed(None),
+        }
+    }
+
+    /// The beat of the pattern which is flashing, if any
+    fn flashing(&self) -> Option<usize> {
+        let (n, at) = self.last_beat?;
+        let length = FLASH.min(self.tempo.interval() / 2);
+        if at.elapsed() < length {
+            Some((n % self.pattern.0.len() as u64) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn draw_boxes(&self, printer: &Printer) {
+        let accent = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        let flashing = self.flashing();
+        let current = self
+            .last_beat
+            .map(|(n, _)| (n % self.pattern.0.len() as u64) as usize);
+        for (i, &beat) in self.pattern.0.iter().enumerate() {
+            let (fill, style) = match beat {
+                _ if flashing == Some(i) && beat == Beat::Accent => ("█", accent),
+                _ if flashing == Some(i) => ("█", ColorStyle::primary()),
+                _ if current == Some(i) => ("▒", ColorStyle::secondary()),
+                Beat::Accent => ("▒", accent),
+                Beat::Normal => ("░", ColorStyle::secondary()),
+                Beat::Rest => ("·", ColorStyle::secondary()),
+            };
+            let x = i * (BOX_SIZE.0 + 1);
+            printer.with_color(style, |printer| {
+                for y in 0..BOX_SIZE.1 {
+                    printer.print((x, y), &fill.repeat(BOX_SIZE.0));
+                }
+            });
+        }
+    }
+}
+
+impl View for MetronomeView {
+    fn draw(&self, printer: &Printer) {
+        let tempo = match self.tempo {
+            Tempo::Bpm(_) => format!(
+                "{}  ({:.3}s per beat)",
+                self.tempo,
+                self.tempo.interval().as_secs_f64()
+            ),
+            Tempo::Every(_) => self.tempo.to_string(),
+        };
+        printer.with_effect(Effect::Bold, |printer| printer.print((0, 0), &tempo));
+
+        self.draw_boxes(&printer.offset((0, 2)));
+
+        let row = BOX_SIZE.1 + 3;
+        match (&self.schedule, self.last_beat) {
+            (Some(_), Some((n, _))) => printer.print(
+                (0, row),
+                &format!(
+                    "Beat {}, bar {}",
+                    n % self.pattern.0.len() as u64 + 1,
+                    n / self.pattern.0.len() as u64 + 1
+                ),
+            ),
+            (Some(_), None) => {}
+            (None, _) => printer.print((0, row), "STOPPED"),
+        }
+        let taps = self.taps.count(Instant::now());
+        if taps > 0 {
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print((0, row + 1), &format!("Tap tempo: {} taps", taps))
+            });
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        let boxes = self.pattern.0.len() * (BOX_SIZE.0 + 1);
+        Vec2::new(boxes.max(32), BOX_SIZE.1 + 5) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Refresh => return self.check_beat(),
+            Event::Char(' ') => self.start_or_stop(),
+            Event::Char('t') => {
+                if let Some(tempo) = self.taps.tap(Instant::now()) {
+                    self.set_tempo(tempo);
+                }
+            }
+            Event::Key(Key::Up) | Event::Char('+') => self.set_tempo(self.tempo.faster(1)),
+            Event::Key(Key::Down) | Event::Char('-') => self.set_tempo(self.tempo.faster(-1)),
+            Event::Key(Key::PageUp) => self.set_tempo(self.tempo.faster(10)),
+            Event::Key(Key::PageDown) => self.set_tempo(self.tempo.faster(-10)),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
@@ -0,0 +1,365 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Meeting Planner TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows a 24-hour strip for the local time zone and each of the given zones, one cell per hour of
+//! the local day, with the hour in that zone. Working hours are shaded green. The slot at the
+//! cursor is highlighted, and the last row marks the slots within everyone's working hours. The
+//! local zone always counts, whether it is among the given zones or not.
+//!
+//! Left/right arrow keys (or "h"/"l") move the cursor by an hour, and "PageUp"/"PageDown" (or
+//! "<"/">") by a day. "n"/"N" jump to the next/previous slot within everyone's working hours.
+//! "Enter" calls the callback set with `on_select()` with the start of the slot, and "y" the
+//! callback set with `on_copy()`. Clicking a cell moves the cursor there.
+
+use crate::world::{zone_label, WorkingHours};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key, MouseButton, MouseEvent},
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use std::rc::Rc;
+
+/// Each cell is two columns wide
+const CELL_WIDTH: usize = 2;
+
+/// Called with the start of the chosen slot
+type SlotCallback = Rc<dyn Fn(&mut Cursive, DateTime<Utc>)>;
+
+pub struct PlannerView {
+    zones: Vec<Tz>,
+    hours: WorkingHours,
+    duration: Duration,
+    /// The local date shown
+    date: NaiveDate,
+    /// The hour of the local day at the cursor
+    cursor: i64,
+    on_select: Option<SlotCallback>,
+    on_copy: Option<SlotCallback>,
+}
+
+impl PlannerView {
+    /// A planner for a one-hour meeting, with the cursor at the current hour
+    pub fn new(zones: Vec<Tz>) -> Self {
+        let now = Local::now();
+        Self {
+            zones,
+            hours: WorkingHours::default(),
+            duration: Duration::hours(1),
+            date: now.date_naive(),
+            cursor: (now - start_of_day(&Local, now.date_naive())).num_hours(),
+            on_select: None,
+            on_copy: None,
+        }
+    }
+
+    /// Sets the working hours, 9-17 by default
+    pub fn with_working_hours(mut self, hours: WorkingHours) -> Self {
+        self.hours = hours;
+        self
+    }
+
+    /// Sets the length of the meeting, an hour by default
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Shows `date` instead of today, with the cursor at the start of the working hours
+    pub fn with_date(mut self, date: NaiveDate) -> Self {
+        self.date = date;
+        self.cursor = self.hours.start as i64;
+        self
+    }
+
+    /// Sets a callback to be used when a slot is chosen with "Enter"
+    pub fn set_on_select<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, DateTime<Utc>) -> R,
+    {
+        self.on_select = Some(Rc::new(move |s, start| {
+            cb(s, start);
+        }));
+    }
+
+    pub fn on_select<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, DateTime<Utc>) -> R,
+    {
+        self.with(|s| s.set_on_select(cb))
+    }
+
+    /// Sets a callback to be used when a slot is copied with "y"
+    pub fn set_on_copy<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, DateTime<Utc>) -> R,
+    {
+        self.on_copy = Some(Rc::new(move |s, start| {
+            cb(s, start);
+        }));
+    }
+
+    pub fn on_copy<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, DateTime<Utc>) -> R,
+    {
+        self.with(|s| s.set_on_copy(cb))
+    }
+
+    /// The start of the `hour`th hour of the local day shown
+    fn hour_start(&self, hour: i64) -> DateTime<Utc> {
+        (start_of_day(&Local, self.date) + Duration::hours(hour)).with_timezone(&Utc)
+    }
+
+    /// The number of cells the meeting covers
+    fn slot_cells(&self) -> i64 {
+        ((self.duration.num_minutes() + 59) / 60).max(1)
+    }
+
+    /// Whether a meeting starting at `start` is within everyone's working hours, including the
+    /// local zone's, checked every quarter of an hour for zones with half-hour offsets
+    fn fits(&self, start: DateTime<Utc>) -> bool {
+        let quarters = (self.duration.num_minutes() + 14) / 15;
+        (0..quarters.max(1)).all(|i| {
+            let t = start + Duration::minutes(i * 15);
+            self.hours.contains(&t.with_timezone(&Local))
+                && self
+                    .zones
+                    .iter()
+                    .all(|zone| self.hours.contains(&t.with_timezone(zone)))
+        })
+    }
+
+    fn move_cursor(&mut self, delta: i64) {
+        self.cursor += delta;
+        while self.cursor < 0 {
+            self.cursor += 24;
+            self.date = self.date.pred_opt().unwrap();
+        }
+        while self.cursor >= 24 {
+            self.cursor -= 24;
+            self.date = self.date.succ_opt().unwrap();
+        }
+    }
+
+    /// Moves the cursor to the next (or previous, if `step` is negative) slot which fits, looking
+    /// up to two weeks ahead
+    fn jump_to_overlap(&mut self, step: i64) {
+        let (date, cursor) = (self.date, self.cursor);
+        for _ in 0..24 * 14 {
+            self.move_cursor(step);
+            if self.fits(self.hour_start(self.cursor)) {
+                return;
+            }
+        }
+        self.date = date;
+        self.cursor = cursor;
+    }
+
+    fn label_width(&self) -> usize {
+        self.zones
+            .iter()
+            .map(|&zone| zone_label(zone).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8)
+    }
+
+    fn draw_strip<Z: TimeZone>(&self, printer: &Printer, row: usize, label: &str, zone: &Z)
+    where
+        Z::Offset: std::fmt::Display,
+    {
+        let working = ColorStyle::new(Color::Dark(BaseColor::Black), Color::Dark(BaseColor::Green));
+        let x0 = self.label_width() + 2;
+        printer.print((0, row), label);
+        for hour in 0..24 {
+            let time = self.hour_start(hour).with_timezone(zone);
+            let style = if (self.cursor..self.cursor + self.slot_cells()).contains(&hour) {
+                ColorStyle::highlight()
+            } else if self.hours.contains(&time) {
+                working
+            } else {
+                ColorStyle::secondary()
+            };
+            printer.with_color(style, |printer| {
+                printer.print(
+                    (x0 + hour as usize * CELL_WIDTH, row),
+                    &time.format("%H").to_string(),
+                )
+            });
+        }
+        let start = self.hour_start(self.cursor).with_timezone(zone);
+        printer.print(
+            (x0 + 24 * CELL_WIDTH + 2, row),
+            &start.format("%a %H:%M").to_string(),
+        );
+    }
+
+    fn on_mouse_event(&mut self, position: Vec2, event: MouseEvent) -> EventResult {
+        let x0 = self.label_width() + 2;
+        let on_strip = (1..self.zones.len() + 3).contains(&position.y)
+            && (x0..x0 + 24 * CELL_WIDTH).contains(&position.x);
+        match event {
+            MouseEvent::Press(MouseButton::Left) if on_strip => {
+                self.cursor = ((position.x - x0) / CELL_WIDTH) as i64;
+            }
+            MouseEvent::WheelUp => self.move_cursor(-1),
+            MouseEvent::WheelDown => self.move_cursor(1),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+
+    /// Calls the callback set with `on_copy()` if `copy` is set, or the one set with `on_select()`
+    fn choose(&self, copy: bool) -> EventResult {
+        let start = self.hour_start(self.cursor);
+        let cb = if copy { &self.on_copy } else { &self.on_select };
+        match cb.clone() {
+            Some(cb) => EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, start)))),
+            None => EventResult::Consumed(None),
+        }
+    }
+}
+
+/// The start of the day in `zone`. Where the change to daylight saving time skips midnight (e.g.
+/// in America/Santiago), the day starts at the first valid time after it.
+fn start_of_day<Z: TimeZone>(zone: &Z, date: NaiveDate) -> DateTime<Z> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    (0..)
+        .map(|minutes| midnight + Duration::minutes(minutes))
+        .find_map(|t| zone.from_local_datetime(&t).earliest())
+        .unwrap()
+}
+
+impl View for PlannerView {
+    fn draw(&self, printer: &Printer) {
+        let start = self.hour_start(self.cursor).with_timezone(&Local);
+        let end = start + self.duration;
+        let header = format!(
+            "{}    {}–{}{}",
+            self.date.format("%a %Y-%m-%d"),
+            start.format("%H:%M"),
+            end.format("%H:%M"),
+            if self.fits(start.with_timezone(&Utc)) {
+                "  fits everyone's working hours"
+            } else {
+                ""
+            }
+        );
+        printer.with_effect(cursive::theme::Effect::Bold, |printer| {
+            printer.print((0, 0), &header)
+        });
+        self.draw_strip(printer, 1, "Local", &Local);
+        for (i, zone) in self.zones.iter().enumerate() {
+            self.draw_strip(printer, i + 2, &zone_label(*zone), zone);
+        }
+        let row = self.zones.len() + 2;
+        let x0 = self.label_width() + 2;
+        printer.print((0, row), "Overlap");
+        let style = ColorStyle::new(Color::Dark(BaseColor::Green), PaletteColor::View);
+        for hour in 0..24 {
+            if self.fits(self.hour_start(hour)) {
+                printer.with_color(style, |printer| {
+                    printer.print((x0 + hour as usize * CELL_WIDTH, row), "██")
+                });
+            }
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        Vec2::new(
+            self.label_width() + 24 * CELL_WIDTH + 13,
+            self.zones.len() + 3,
+        ) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        if let Event::Mouse {
+            offset,
+            position,
+            event,
+        } = event
+        {
+            return match position.checked_sub(offset) {
+                Some(position) => self.on_mouse_event(position, event),
+                None => EventResult::Ignored,
+            };
+        }
+        match event {
+            Event::Key(Key::Left) | Event::Char('h') => self.move_cursor(-1),
+            Event::Key(Key::Right) | Event::Char('l') => self.move_cursor(1),
+            Event::Key(Key::PageUp) | Event::Char('<') => self.move_cursor(-24),
+            Event::Key(Key::PageDown) | Event::Char('>') => self.move_cursor(24),
+            Event::Key(Key::Home) => self.cursor = 0,
+            Event::Key(Key::End) => self.cursor = 23,
+            Event::Char('n') => self.jump_to_overlap(1),
+            Event::Char('N') => self.jump_to_overlap(-1),
+            Event::Key(Key::Enter) => return self.choose(false),
+            Event::Char('y') => return self.choose(true),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+    use chrono_tz::{America, Asia, Europe};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn days_start_at_midnight() {
+        let start = start_of_day(&Europe::Berlin, date(2020, 3, 29));
+        assert_eq!((start.hour(), start.minute()), (0, 0));
+        assert_eq!(start.with_timezone(&Utc).hour(), 23);
+        let start = start_of_day(&Utc, date(2020, 3, 29));
+        assert_eq!(start, Utc.with_ymd_and_hms(2020, 3, 29, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn daylight_saving_time_skips_midnight() {
+        // clocks went from 00:00 straight to 01:00
+        for (zone, date) in &[
+            (America::Santiago, date(2020, 9, 6)),
+            (Asia::Beirut, date(2020, 3, 29)),
+            (America::Havana, date(2020, 3, 8)),
+        ] {
+            let start = start_of_day(zone, *date);
+            assert_eq!(start.date_naive(), *date, "{}", zone);
+            assert_eq!((start.hour(), start.minute()), (1, 0), "{}", zone);
+            // the day before still lasts 24 hours, as it ends at 01:00
+            let yesterday = start_of_day(zone, date.pred_opt().unwrap());
+            assert_eq!(start - yesterday, Duration::hours(24), "{}", zone);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Speedrun Split Timer TUI
+//!
+//! ## Expected Behavior
+//!
+//! Press "Space" to start the run, and to pause/resume afterwards. Press "l" to split, i.e. to finish the current segment. Press "Enter" to reset the run before the last segment.
+//!
+//! Each finished segment shows its split time and the difference from the personal best (green when ahead, red when behind, yellow when the segment was faster than ever before). The current segment shows the difference as soon as it falls behind the personal best.
+//!
+//! When the last segment is finished or the run is reset, the splits are updated and the callback set with `on_finish()` is called.
+
+use crate::splits::{RunResult, Splits};
+use crate::utils::signed_hhmmssxxx;
+use chrono::Duration;
+use clock_core::stopwatch::Stopwatch;
+use cursive::{
+    event::{Callback, Event, EventResult, Key},
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use hhmmss::Hhmmss;
+use std::rc::Rc;
+
+/// Called with the updated splits and the result of the run when it finishes
+type OnFinish = Rc<dyn Fn(&mut Cursive, Splits, RunResult)>;
+
+pub struct SplitsView {
+    stopwatch: Stopwatch,
+    splits: Splits,
+    started: bool,
+    paused: bool,
+    on_finish: Option<OnFinish>,
+}
+
+impl SplitsView {
+    pub fn new(splits: Splits) -> Self {
+        Self {
+            stopwatch: Stopwatch::default(),
+            splits,
+            started: false,
+            paused: true,
+            on_finish: None,
+        }
+    }
+
+    /// Sets a callback to be used when the last segment is finished or the run is reset
+    ///
+    /// The updated splits and the result of the run will be given to the callback.
+    pub fn set_on_finish<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Splits, RunResult) -> R,
+    {
+        self.on_finish = Some(Rc::new(move |s, splits, result| {
+            cb(s, splits, result);
+        }));
+    }
+
+    pub fn on_finish<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Splits, RunResult) -> R,
+    {
+        self.with(|s| s.set_on_finish(cb))
+    }
+
+    fn pause_or_resume(&mut self) {
+        self.stopwatch.pause_or_resume();
+        self.started = true;
+        self.paused = !self.paused;
+    }
+
+    fn split(&mut self) -> EventResult {
+        if !self.started || self.paused {
+            return EventResult::Consumed(None);
+        }
+        self.stopwatch.lap();
+        if self.stopwatch.data.laps.len() >= self.splits.segments.len() {
+            self.finish()
+        } else {
+            EventResult::Consumed(None)
+        }
+    }
+
+    fn finish(&mut self) -> EventResult {
+        let segment_times = self.stopwatch.data.laps.clone();
+        self.stopwatch = Stopwatch::default();
+        self.started = false;
+        self.paused = true;
+        let result = self.splits.record_run(segment_times);
+        let splits = self.splits.clone();
+        if self.on_finish.is_some() {
+            let cb = self.on_finish.clone().unwrap();
+            EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, splits, result))))
+        } else {
+            EventResult::Consumed(None)
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.splits
+            .segments
+            .iter()
+            .map(|segment| segment.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(12)
+    }
+}
+
+impl View for SplitsView {
+    fn draw(&self, printer: &Printer) {
+        let ahead = ColorStyle::new(Color::Dark(BaseColor::Green), PaletteColor::View);
+        let behind = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        let best = ColorStyle::new(Color::Light(BaseColor::Yellow), PaletteColor::View);
+
+        printer.print((0, 0), &self.splits.title());
+        let laps = &self.stopwatch.data.laps;
+        let elapsed = self.stopwatch.read();
+        let name_width = self.name_width();
+        let mut split = Duration::zero();
+        for (i, segment) in self.splits.segments.iter().enumerate() {
+            let row = i + 1;
+            let style = if i == laps.len() && self.started {
+                ColorStyle::highlight()
+            } else {
+                ColorStyle::primary()
+            };
+            printer.with_color(style, |printer| printer.print((0, row), &segment.name));
+
+            let (split_time, segment_time) = if i < laps.len() {
+                split += laps[i];
+                (split, Some(laps[i]))
+            } else if i == laps.len() && self.started {
+                (elapsed, None)
+            } else {
+                // not reached yet: show the personal best split
+                if let Some(pb) = segment.personal_best {
+                    printer.print((name_width + 16, row), &pb.hhmmssxxx());
+                }
+                continue;
+            };
+
+            printer.print((name_width + 16, row), &split_time.hhmmssxxx());
+            if let Some(pb) = segment.personal_best {
+                let delta = split_time - pb;
+                // the current segment only shows the delta when falling behind
+                if segment_time.is_some() || delta > Duration::zero() {
+                    let style = match segment_time {
+                        Some(t) if segment.best_segment.map_or(false, |b| t < b) => best,
+                        _ if delta > Duration::zero() => behind,
+                        _ => ahead,
+                    };
+                    printer.with_color(style, |printer| {
+                        printer.print((name_width + 2, row), &signed_hhmmssxxx(delta))
+                    });
+                }
+            }
+        }
+
+        let footer = self.splits.segments.len() + 2;
+        printer.print((0, footer), "Time:");
+        printer.print((name_width + 16, footer), &elapsed.hhmmssxxx());
+        if self.started && self.paused {
+            printer.print((name_width + 2, footer), "PAUSED");
+        }
+        printer.print((0, footer + 1), "Personal best:");
+        if let Some(pb) = self.splits.personal_best() {
+            printer.print((name_width + 16, footer + 1), &pb.hhmmssxxx());
+        }
+        printer.print((0, footer + 2), "Sum of best:");
+        if let Some(sob) = self.splits.sum_of_best() {
+            printer.print((name_width + 16, footer + 2), &sob.hhmmssxxx());
+        }
+        // the best possible time, given the current run so far
+        if self.started && laps.len() < self.splits.segments.len() {
+            let sum_of_best = self.splits.sum_of_best();
+            let sum_of_best_done = self.splits.sum_of_best_until(laps.len());
+            if let (Some(total), Some(done)) = (sum_of_best, sum_of_best_done) {
+                let remaining = total - done;
+                printer.print((0, footer + 3), "Best possible:");
+                printer.print(
+                    (name_width + 16, footer + 3),
+                    &(split + remaining).hhmmssxxx(),
+                );
+            }
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        let width = std::cmp::max(self.name_width() + 28, self.splits.title().len());
+        Vec2::new(width, self.splits.segments.len() + 6) // columns, rows (width, height)
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Char(' ') => {
+                self.pause_or_resume();
+            }
+            Event::Char('l') => {
+                return self.split();
+            }
+            // reset
+            Event::Key(Key::Enter) => {
+                if self.started {
+                    return self.finish();
+                }
+            }
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
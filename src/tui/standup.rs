@@ -0,0 +1,260 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Standup Speaker Timer TUI
+//!
+//! ## Expected Behavior
+//!
+//! Runs a `TimerView` with a progress bar for each speaker in turn, counting down their time box.
+//! Once the time box is used up, the time spoken over it is shown in red instead. Press "n" or
+//! "Enter" to go to the next speaker, and "Space" to pause/resume. The queue is listed below, with
+//! how long each speaker who is done spoke compared with their time box.
+//!
+//! After the last speaker, the callback set with `on_finish()` is called with the speakers and the
+//! time they spoke.
+
+use super::TimerView;
+use crate::standup::Speaker;
+use chrono::Duration;
+use clock_core::stopwatch::Stopwatch;
+use cursive::{
+    direction::Direction,
+    event::{Callback, Event, EventResult, Key},
+    theme::{BaseColor, Color, ColorStyle, Effect, PaletteColor},
+    view::View,
+    Cursive, Printer, Vec2, With,
+};
+use hhmmss::Hhmmss;
+use std::rc::Rc;
+
+/// The rows of the timer, without its buttons: the remaining time, the progress bar and the stats
+const TIMER_ROWS: usize = 3;
+
+/// Called with the speakers and the time they spoke after the last speaker
+type OnFinish = Rc<dyn Fn(&mut Cursive, Vec<Speaker>)>;
+
+pub struct StandupView {
+    speakers: Vec<Speaker>,
+    /// The index of the current speaker, or the number of speakers when everyone has spoken
+    current: usize,
+    timer: TimerView,
+    /// The time the current speaker has spoken, which goes on past the time box
+    stopwatch: Stopwatch,
+    paused: bool,
+    on_finish: Option<OnFinish>,
+}
+
+impl StandupView {
+    /// Starts the timer of the first speaker right away
+    pub fn new(speakers: Vec<Speaker>) -> Self {
+        let mut view = Self {
+            speakers,
+            current: 0,
+            timer: TimerView::new(Duration::zero()),
+            stopwatch: Stopwatch::default(),
+            paused: false,
+            on_finish: None,
+        };
+        view.start_speaker();
+        view
+    }
+
+    /// Sets a callback to be used when the last speaker is done
+    pub fn set_on_finish<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Vec<Speaker>) -> R,
+    {
+        self.on_finish = Some(Rc::new(move |s, speakers| {
+            cb(s, speakers);
+        }));
+    }
+
+    pub fn on_finish<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Vec<Speaker>) -> R,
+    {
+        self.with(|s| s.set_on_finish(cb))
+    }
+
+    /// The speakers, with the time spoken so far by the current one
+    pub fn speakers(&self) -> Vec<Speaker> {
+        let mut speakers = self.speakers.clone();
+        if let Some(speaker) = speakers.get_mut(self.current) {
+            speaker.spoken = Some(self.stopwatch.read());
+        }
+        speakers
+    }
+
+    fn is_finished(&self) -> bool {
+        self.current >= self.speakers.len()
+    }
+
+    fn start_speaker(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        self.timer = TimerView::new(self.speakers[self.current].allotment).with_progress_bar();
+        self.timer.start();
+        self.stopwatch = Stopwatch::default();
+        self.stopwatch.pause_or_resume();
+        self.paused = false;
+    }
+
+    fn pause_or_resume(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        self.stopwatch.pause_or_resume();
+        self.paused = !self.paused;
+        // once the time box is used up, the timer has finished and only the stopwatch goes on
+        self.timer.set_paused(self.paused);
+    }
+
+    fn next_speaker(&mut self) -> EventResult {
+        if self.is_finished() {
+            return EventResult::Consumed(None);
+        }
+        self.speakers[self.current].spoken = Some(self.stopwatch.read());
+        self.current += 1;
+        if !self.is_finished() {
+            self.start_speaker();
+            return EventResult::Consumed(None);
+        }
+        let speakers = self.speakers.clone();
+        match self.on_finish.clone() {
+            Some(cb) => {
+                EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, speakers))))
+            }
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.speakers
+            .iter()
+            .map(|s| s.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8)
+    }
+
+    fn draw_current(&self, printer: &Printer) {
+        let speaker = &self.speakers[self.current];
+        printer.with_effect(Effect::Bold, |printer| {
+            printer.print(
+                (0, 0),
+                &format!(
+                    "{} ({} of {})",
+                    speaker.name,
+                    self.current + 1,
+                    self.speakers.len()
+                ),
+            )
+        });
+        if let Some(next) = self.speakers.get(self.current + 1) {
+            let next = format!("next: {}", next.name);
+            let x = printer.size.x.saturating_sub(next.chars().count());
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print((x, 0), &next)
+            });
+        }
+
+        let spoken = self.stopwatch.read();
+        if spoken < speaker.allotment {
+            self.timer
+                .draw(&printer.offset((0, 1)).cropped((printer.size.x, TIMER_ROWS)));
+            return;
+        }
+        let red = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        printer.with_color(red, |printer| {
+            printer.print(
+                (0, 1),
+                &format!("overtime +{}", (spoken - speaker.allotment).hhmmss()),
+            );
+            printer.print((0, 2), &"█".repeat(printer.size.x));
+        });
+        let mut stats = format!(
+            "{} spoken, time box {}",
+            spoken.hhmmss(),
+            speaker.allotment.hhmmss()
+        );
+        if self.paused {
+            stats.push_str("  paused");
+        }
+        printer.print((0, 3), &stats);
+    }
+}
+
+impl View for StandupView {
+    fn draw(&self, printer: &Printer) {
+        if self.is_finished() {
+            printer.print((0, 0), "Everyone has spoken");
+        } else {
+            self.draw_current(printer);
+        }
+
+        let ahead = ColorStyle::new(Color::Dark(BaseColor::Green), PaletteColor::View);
+        let behind = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+        let name_width = self.name_width();
+        for (i, speaker) in self.speakers.iter().enumerate() {
+            let row = i + TIMER_ROWS + 2;
+            let marker = if i == self.current { "▶" } else { " " };
+            printer.print((0, row), &format!("{} {}", marker, speaker.name));
+            let x = name_width + 4;
+            match speaker.spoken {
+                Some(spoken) => {
+                    let style = if spoken > speaker.allotment {
+                        behind
+                    } else {
+                        ahead
+                    };
+                    printer.with_color(style, |printer| {
+                        printer.print((x, row), &spoken.hhmmss());
+                    });
+                    printer.print((x + 9, row), &format!("/ {}", speaker.allotment.hhmmss()));
+                }
+                None => printer.with_color(ColorStyle::secondary(), |printer| {
+                    printer.print((x + 9, row), &format!("/ {}", speaker.allotment.hhmmss()))
+                }),
+            }
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        Vec2::new(
+            (self.name_width() + 24).max(48),
+            self.speakers.len() + TIMER_ROWS + 2,
+        ) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Refresh => {
+                self.timer.on_event(Event::Refresh);
+                return EventResult::Ignored;
+            }
+            Event::Char(' ') => self.pause_or_resume(),
+            Event::Char('n') | Event::Key(Key::Enter) => return self.next_speaker(),
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
@@ -15,22 +15,121 @@
 // You should have received a copy of the GNU General Public License
 // along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
 
-use hhmmss::Hhmmss;
-// use chrono::{DateTime, Duration, Local};
+use super::buttons::{button_at, draw_buttons};
+use crate::utils::signed_hhmmssxxx;
+use chrono::{DateTime, Duration, Local};
 use clock_core::stopwatch::{Stopwatch, StopwatchData};
 use cursive::{
-    event::{Callback, Event, EventResult, Key, MouseEvent},
+    event::{Callback, Event, EventResult, Key, MouseButton, MouseEvent},
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
     view::View,
     Cursive, Printer, Vec2, With,
 };
+use hhmmss::Hhmmss;
 use std::rc::Rc;
 
+/// The column displayed to the right of each lap time. Press `m` to cycle through them.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum LapColumn {
+    /// Difference from the previous lap
+    #[default]
+    DeltaPrevious,
+    /// Difference from the average of all laps
+    DeltaAverage,
+    /// Cumulative time at the end of the lap
+    Split,
+}
+
+impl LapColumn {
+    fn next(self) -> Self {
+        match self {
+            LapColumn::DeltaPrevious => LapColumn::DeltaAverage,
+            LapColumn::DeltaAverage => LapColumn::Split,
+            LapColumn::Split => LapColumn::DeltaPrevious,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            LapColumn::DeltaPrevious => "vs. previous",
+            LapColumn::DeltaAverage => "vs. average",
+            LapColumn::Split => "split",
+        }
+    }
+}
+
+/// A change to the stopwatch which can be undone
+enum Edit {
+    /// A lap was added at the end
+    Lap,
+    /// The stopwatch was paused or resumed (`running` is the state afterwards) at `at`
+    PauseOrResume { at: DateTime<Local>, running: bool },
+    /// The lap at `index`, which ended at `moment`, was deleted, and its time merged into the next
+    /// lap
+    Delete {
+        index: usize,
+        lap: Duration,
+        label: Option<String>,
+        moment: DateTime<Local>,
+    },
+    /// The laps at `index` and `index + 1`, the first of which ended at `moment`, were merged into
+    /// one
+    Merge {
+        index: usize,
+        laps: (Duration, Duration),
+        labels: (Option<String>, Option<String>),
+        moment: DateTime<Local>,
+    },
+    /// The lap at `index` was split into two
+    Split { index: usize },
+}
+
+/// What the input line at the bottom of the view is asking for
+#[derive(Copy, Clone)]
+enum Prompt {
+    /// The label of the lap at the index
+    Label(usize),
+    /// Where to split the lap at the index
+    Split(usize),
+}
+
+/// The data of a stopped stopwatch, together with the labels the user gave to its laps.
+#[derive(Debug)]
+pub struct StopwatchRecord {
+    pub data: StopwatchData,
+    /// One (optional) label for each lap in `data.laps`
+    pub lap_labels: Vec<Option<String>>,
+}
+
+impl StopwatchRecord {
+    pub fn lap_label(&self, i: usize) -> Option<&str> {
+        self.lap_labels.get(i).and_then(|label| label.as_deref())
+    }
+}
+
+/// Called with the record of the stopwatch when it stops
+type OnStop = Rc<dyn Fn(&mut Cursive, StopwatchRecord)>;
+
+/// Called with the number of the lap which goes over the target
+type OnTargetLapExceeded = Rc<dyn Fn(&mut Cursive, usize)>;
+
 #[derive(Default)]
 pub struct StopwatchView {
     stopwatch: Stopwatch,
-    on_stop: Option<Rc<dyn Fn(&mut Cursive, StopwatchData)>>,
+    on_stop: Option<OnStop>,
     show_laps: usize,
     show_laps_offset: usize,
+    lap_column: LapColumn,
+    lap_labels: Vec<Option<String>>,
+    selected_lap: Option<usize>,
+    prompt: Option<(Prompt, String)>, // (what is asked for, input)
+    prompt_error: Option<String>,
+    running: bool,
+    offset: Duration, // time added or removed by undoing pauses/resumes
+    undo_stack: Vec<Edit>,
+    target_lap: Option<Duration>,
+    target_lap_exceeded: bool, // whether the current lap is over the target (and has been reported)
+    on_target_lap_exceeded: Option<OnTargetLapExceeded>,
 }
 
 impl StopwatchView {
@@ -43,9 +142,46 @@ impl StopwatchView {
         self
     }
 
+    /// Starts the stopwatch right away, instead of waiting for "Space"
+    pub fn running(mut self) -> Self {
+        self.stopwatch.pause_or_resume();
+        self.running = true;
+        self
+    }
+
+    pub fn with_lap_column(mut self, column: LapColumn) -> Self {
+        self.lap_column = column;
+        self
+    }
+
+    /// Shows how far the current lap is ahead of or behind `target`
+    pub fn with_target_lap(mut self, target: Duration) -> Self {
+        self.target_lap = Some(target);
+        self
+    }
+
+    /// Sets a callback to be used when the current lap goes over the target lap time
+    ///
+    /// The number of the lap (starting from 1) will be given to the callback.
+    pub fn set_on_target_lap_exceeded<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, usize) -> R,
+    {
+        self.on_target_lap_exceeded = Some(Rc::new(move |s, n| {
+            cb(s, n);
+        }));
+    }
+
+    pub fn on_target_lap_exceeded<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, usize) -> R,
+    {
+        self.with(|s| s.set_on_target_lap_exceeded(cb))
+    }
+
     pub fn set_on_stop<F, R>(&mut self, cb: F)
     where
-        F: 'static + Fn(&mut Cursive, StopwatchData) -> R,
+        F: 'static + Fn(&mut Cursive, StopwatchRecord) -> R,
     {
         self.on_stop = Some(Rc::new(move |s, t| {
             cb(s, t);
@@ -54,16 +190,45 @@ impl StopwatchView {
 
     pub fn on_stop<F, R>(self, cb: F) -> Self
     where
-        F: 'static + Fn(&mut Cursive, StopwatchData) -> R,
+        F: 'static + Fn(&mut Cursive, StopwatchRecord) -> R,
     {
         self.with(|s| s.set_on_stop(cb))
     }
 
     fn stop(&mut self) -> EventResult {
-        let data = self.stopwatch.stop();
+        if !self.started() {
+            return EventResult::Consumed(None);
+        }
+        let n = self.stopwatch.data.laps.len();
+        let paused = self.stopwatch.paused;
+        // `Stopwatch::stop()` counts the time since the last start even when it is paused
+        let elapsed = self.elapsed();
+        let mut data = self.stopwatch.stop();
+        data.elapsed = elapsed;
+        if paused {
+            // the stopwatch stopped when it was paused
+            data.pause_moments.pop();
+        }
+        if data.laps.len() > n {
+            // the final lap ends when the stopwatch stops
+            let before = data.laps[..n]
+                .iter()
+                .fold(Duration::zero(), |acc, &lap| acc + lap);
+            data.laps[n] = data.elapsed - before;
+        }
+        // the labels go with the laps, and the next run starts without any
+        let mut lap_labels = std::mem::take(&mut self.lap_labels);
+        lap_labels.resize(data.laps.len(), None);
+        // the edits, the selection and the undone pauses belong to the run which has stopped
+        self.undo_stack.clear();
+        self.offset = Duration::zero();
+        self.running = false;
+        self.selected_lap = None;
+        self.target_lap_exceeded = false;
+        let record = StopwatchRecord { data, lap_labels };
         if self.on_stop.is_some() {
             let cb = self.on_stop.clone().unwrap();
-            EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, data))))
+            EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, record))))
         } else {
             EventResult::Consumed(None)
         }
@@ -80,57 +245,532 @@ impl StopwatchView {
             self.show_laps_offset -= 1;
         }
     }
+
+    fn lap(&mut self) {
+        let n = self.stopwatch.data.laps.len();
+        self.stopwatch.lap();
+        if self.stopwatch.data.laps.len() == n {
+            return; // paused
+        }
+        // the laps always add up to the elapsed time, even after they have been edited
+        self.stopwatch.data.laps[n] = self.current_lap();
+        self.lap_labels.resize(n + 1, None);
+        self.undo_stack.push(Edit::Lap);
+        self.show_laps_offset = 0;
+        self.selected_lap = None;
+        self.target_lap_exceeded = false;
+    }
+
+    fn pause_or_resume(&mut self) {
+        self.stopwatch.pause_or_resume();
+        self.running = !self.running;
+        self.undo_stack.push(Edit::PauseOrResume {
+            at: Local::now(),
+            running: self.running,
+        });
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.stopwatch.read() + self.offset
+    }
+
+    /// The time elapsed since the last lap
+    fn current_lap(&self) -> Duration {
+        let laps = &self.stopwatch.data.laps;
+        self.elapsed() - laps.iter().fold(Duration::zero(), |acc, &lap| acc + lap)
+    }
+
+    /// Deletes the selected lap. Its time is merged into the next lap (or the current lap).
+    fn delete_lap(&mut self) {
+        let index = match self.selected_lap {
+            Some(i) => i,
+            None => return,
+        };
+        let data = &mut self.stopwatch.data;
+        let lap = data.laps.remove(index);
+        if index < data.laps.len() {
+            data.laps[index] += lap;
+        }
+        let moment = data.lap_moments.remove(index);
+        let label = self.lap_labels.remove(index);
+        self.undo_stack.push(Edit::Delete {
+            index,
+            lap,
+            label,
+            moment,
+        });
+        self.after_edit();
+    }
+
+    /// Merges the selected lap with the previous one
+    fn merge_laps(&mut self) {
+        let index = match self.selected_lap {
+            Some(i) if i > 0 => i - 1,
+            _ => return,
+        };
+        let data = &mut self.stopwatch.data;
+        let next = data.laps.remove(index + 1);
+        let merged = (data.laps[index], next);
+        data.laps[index] += next;
+        let moment = data.lap_moments.remove(index);
+        let next_label = self.lap_labels.remove(index + 1);
+        let labels = (self.lap_labels[index].clone(), next_label.clone());
+        if self.lap_labels[index].is_none() {
+            self.lap_labels[index] = next_label;
+        }
+        self.undo_stack.push(Edit::Merge {
+            index,
+            laps: merged,
+            labels,
+            moment,
+        });
+        self.selected_lap = Some(index);
+        self.after_edit();
+    }
+
+    /// Splits the lap at `index` into two, the first of which is `first` long
+    fn split_lap(&mut self, index: usize, first: Duration) -> Result<(), String> {
+        let data = &mut self.stopwatch.data;
+        if first <= Duration::zero() || first >= data.laps[index] {
+            return Err(format!(
+                "must be shorter than {}",
+                data.laps[index].hhmmssxxx()
+            ));
+        }
+        let second = data.laps[index] - first;
+        data.laps[index] = first;
+        data.laps.insert(index + 1, second);
+        // as if the first lap had been taken then, assuming no pause during the second one
+        let moment = data.lap_moments[index] - second;
+        data.lap_moments.insert(index, moment);
+        self.lap_labels.insert(index + 1, None);
+        self.undo_stack.push(Edit::Split { index });
+        self.after_edit();
+        Ok(())
+    }
+
+    fn undo(&mut self) {
+        let data = &mut self.stopwatch.data;
+        let (laps, moments) = (&mut data.laps, &mut data.lap_moments);
+        match self.undo_stack.pop() {
+            None => return,
+            // the time of the lap goes back to the current lap
+            Some(Edit::Lap) => {
+                laps.pop();
+                moments.pop();
+                self.lap_labels.pop();
+            }
+            // as if the stopwatch had not been paused/resumed
+            Some(Edit::PauseOrResume { at, running }) => {
+                let since = Local::now() - at;
+                self.offset = if running {
+                    self.offset - since
+                } else {
+                    self.offset + since
+                };
+                self.stopwatch.pause_or_resume();
+                self.running = !self.running;
+            }
+            Some(Edit::Delete {
+                index,
+                lap,
+                label,
+                moment,
+            }) => {
+                if index < laps.len() {
+                    laps[index] -= lap;
+                }
+                laps.insert(index, lap);
+                moments.insert(index, moment);
+                self.lap_labels.insert(index, label);
+            }
+            Some(Edit::Merge {
+                index,
+                laps: (first, second),
+                labels: (first_label, second_label),
+                moment,
+            }) => {
+                laps[index] = first;
+                laps.insert(index + 1, second);
+                moments.insert(index, moment);
+                self.lap_labels[index] = first_label;
+                self.lap_labels.insert(index + 1, second_label);
+            }
+            Some(Edit::Split { index }) => {
+                let second = laps.remove(index + 1);
+                laps[index] += second;
+                moments.remove(index);
+                self.lap_labels.remove(index + 1);
+            }
+        }
+        self.after_edit();
+    }
+
+    /// Keeps the selection and the scroll position within the (edited) list of laps
+    fn after_edit(&mut self) {
+        let n = self.stopwatch.data.laps.len();
+        self.selected_lap = self.selected_lap.filter(|&i| i < n);
+        self.show_laps_offset =
+            std::cmp::min(self.show_laps_offset, n.saturating_sub(self.show_laps));
+    }
+
+    fn check_target_lap(&mut self) -> EventResult {
+        match self.target_lap {
+            Some(target) if !self.target_lap_exceeded && self.current_lap() > target => {
+                self.target_lap_exceeded = true;
+                let n = self.stopwatch.data.laps.len() + 1;
+                match self.on_target_lap_exceeded.clone() {
+                    Some(cb) => {
+                        EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, n))))
+                    }
+                    None => EventResult::Consumed(None),
+                }
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Whether the stopwatch has been started since it was created or last stopped
+    fn started(&self) -> bool {
+        !self.stopwatch.data.start_moments.is_empty()
+    }
+
+    /// The labels of the buttons below the lap list. There is nothing to stop before the
+    /// stopwatch has started.
+    fn buttons(&self) -> &'static [&'static str] {
+        if self.running {
+            &["Pause", "Lap", "Stop"]
+        } else if !self.started() {
+            &["Start", "Lap"]
+        } else if self.elapsed() == Duration::zero() {
+            &["Start", "Lap", "Stop"]
+        } else {
+            &["Resume", "Lap", "Stop"]
+        }
+    }
+
+    fn press_button(&mut self, label: &str) -> EventResult {
+        match label {
+            "Start" | "Pause" | "Resume" => self.pause_or_resume(),
+            "Lap" => self.lap(),
+            "Stop" => {
+                self.show_laps_offset = 0;
+                return self.stop();
+            }
+            _ => unreachable!(),
+        }
+        EventResult::Consumed(None)
+    }
+
+    /// The number of rows above the lap list
+    fn header_height(&self) -> usize {
+        if self.target_lap.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Moves the selection by `delta` laps (positive is towards newer laps, which are on top)
+    fn move_selection(&mut self, delta: isize) {
+        let n = self.stopwatch.data.laps.len();
+        if n == 0 {
+            return;
+        }
+        let selected = match self.selected_lap {
+            // select the topmost visible lap first
+            None => n - 1 - self.show_laps_offset,
+            Some(i) => (i as isize + delta).max(0).min(n as isize - 1) as usize,
+        };
+        self.selected_lap = Some(selected);
+        // scroll so that the selected lap is visible
+        if selected + self.show_laps_offset >= n {
+            self.show_laps_offset = n - 1 - selected;
+        } else if selected + self.show_laps_offset + self.show_laps < n {
+            self.show_laps_offset = n - self.show_laps - selected;
+        }
+    }
+
+    /// Index of the lap displayed on the `row`th row
+    fn lap_at_row(&self, row: usize) -> Option<usize> {
+        let len = self.stopwatch.data.laps.len() - self.show_laps_offset;
+        let row = (row + 1).checked_sub(self.header_height())?;
+        if row == 0 || row > std::cmp::min(len, self.show_laps) {
+            None
+        } else {
+            Some(len - row)
+        }
+    }
+
+    /// The selected lap, or the latest lap if none is selected
+    fn selected_or_latest_lap(&self) -> Option<usize> {
+        self.selected_lap
+            .or_else(|| self.stopwatch.data.laps.len().checked_sub(1))
+    }
+
+    /// Starts editing the label of the selected lap, or of the latest lap if none is selected
+    fn edit_label(&mut self) {
+        if let Some(lap) = self.selected_or_latest_lap() {
+            let input = self.lap_labels[lap].clone().unwrap_or_default();
+            self.prompt = Some((Prompt::Label(lap), input));
+        }
+    }
+
+    fn on_prompt_event(&mut self, event: Event) -> EventResult {
+        let (prompt, input) = self.prompt.as_mut().unwrap();
+        match event {
+            Event::Char(c) => input.push(c),
+            Event::Key(Key::Backspace) => {
+                input.pop();
+            }
+            Event::Key(Key::Enter) => {
+                let input = input.trim().to_owned();
+                match *prompt {
+                    Prompt::Label(lap) => {
+                        self.lap_labels[lap] = if input.is_empty() { None } else { Some(input) };
+                    }
+                    Prompt::Split(lap) => {
+                        let first = input
+                            .parse::<humantime::Duration>()
+                            .map_err(|e| e.to_string())
+                            .and_then(|d| Duration::from_std(d.into()).map_err(|e| e.to_string()))
+                            .and_then(|first| self.split_lap(lap, first));
+                        if let Err(e) = first {
+                            self.prompt_error = Some(e);
+                            return EventResult::Consumed(None);
+                        }
+                    }
+                }
+                self.prompt = None;
+                self.prompt_error = None;
+            }
+            Event::Key(Key::Esc) => {
+                self.prompt = None;
+                self.prompt_error = None;
+            }
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+
+    /// Indices of the fastest and the slowest laps. Only meaningful when there are at least two laps.
+    fn best_and_worst_laps(&self) -> Option<(usize, usize)> {
+        let laps = &self.stopwatch.data.laps;
+        if laps.len() < 2 {
+            return None;
+        }
+        let best = laps.iter().enumerate().min_by_key(|(_, lap)| **lap)?.0;
+        let worst = laps.iter().enumerate().max_by_key(|(_, lap)| **lap)?.0;
+        Some((best, worst))
+    }
+
+    fn lap_column_text(&self, i: usize) -> String {
+        let laps = &self.stopwatch.data.laps;
+        match self.lap_column {
+            LapColumn::DeltaPrevious => {
+                if i == 0 {
+                    String::new()
+                } else {
+                    signed_hhmmssxxx(laps[i] - laps[i - 1])
+                }
+            }
+            LapColumn::DeltaAverage => {
+                let total = laps.iter().fold(Duration::zero(), |acc, &lap| acc + lap);
+                signed_hhmmssxxx(laps[i] - total / laps.len() as i32)
+            }
+            LapColumn::Split => laps[..=i]
+                .iter()
+                .fold(Duration::zero(), |acc, &lap| acc + lap)
+                .hhmmssxxx(),
+        }
+    }
 }
 impl View for StopwatchView {
     fn draw(&self, printer: &Printer) {
-        printer.print((4, 0), &self.stopwatch.read().hhmmssxxx());
+        printer.print((4, 0), &self.elapsed().hhmmssxxx());
+        if !self.stopwatch.data.laps.is_empty() {
+            printer.print((22, 0), self.lap_column.title());
+        }
+        if let Some(target) = self.target_lap {
+            let current = self.current_lap();
+            let color = if current > target {
+                ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View)
+            } else if current * 10 >= target * 9 {
+                // approaching the target
+                ColorStyle::new(Color::Dark(BaseColor::Yellow), PaletteColor::View)
+            } else {
+                ColorStyle::primary()
+            };
+            printer.with_color(color, |printer| {
+                printer.print(
+                    (0, 1),
+                    &format!(
+                        "Lap {:02}: {}",
+                        self.stopwatch.data.laps.len() + 1,
+                        current.hhmmssxxx()
+                    ),
+                );
+                printer.print((21, 1), &signed_hhmmssxxx(current - target));
+            });
+            printer.print((35, 1), "vs. target");
+        }
+        // the lap list is below the header
+        let printer = &printer.offset((0, self.header_height() - 1));
 
+        let best_and_worst = self.best_and_worst_laps();
         let len = self.stopwatch.data.laps.len() - self.show_laps_offset;
         let mut i = 0;
         while i < std::cmp::min(len, self.show_laps) {
             i += 1;
 
-            printer.print(
-                (0, i),
-                &[
-                    format!("Lap {:02}: ", len - i + 1),
-                    self.stopwatch.data.laps[len - i].hhmmssxxx(),
-                ]
-                .concat(),
-            );
+            let lap = len - i;
+            let color = match best_and_worst {
+                _ if self.selected_lap == Some(lap) => ColorStyle::highlight(),
+                Some((best, _)) if best == lap => {
+                    ColorStyle::new(Color::Dark(BaseColor::Green), PaletteColor::View)
+                }
+                Some((_, worst)) if worst == lap => {
+                    ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View)
+                }
+                _ => ColorStyle::primary(),
+            };
+            printer.with_color(color, |printer| {
+                printer.print(
+                    (0, i),
+                    &[
+                        format!("Lap {:02}: ", lap + 1),
+                        self.stopwatch.data.laps[lap].hhmmssxxx(),
+                    ]
+                    .concat(),
+                );
+            });
+            printer.print((21, i), &self.lap_column_text(lap));
+            if let Some(label) = &self.lap_labels[lap] {
+                printer.print((35, i), label);
+            }
         }
         if len != i {
             printer.print((0, self.show_laps), ":                           ");
         }
+        draw_buttons(printer, self.show_laps + 1, self.buttons());
+        if let Some((prompt, input)) = &self.prompt {
+            let prompt = match prompt {
+                Prompt::Label(lap) => format!("Label for lap {:02}: ", lap + 1),
+                Prompt::Split(lap) => format!("Split lap {:02} after: ", lap + 1),
+            };
+            printer.print((0, self.show_laps + 2), &prompt);
+            printer.with_color(ColorStyle::highlight(), |printer| {
+                printer.print((prompt.len(), self.show_laps + 2), &format!("{}_", input));
+            });
+            if let Some(error) = &self.prompt_error {
+                printer.with_color(
+                    ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View),
+                    |printer| printer.print((0, self.show_laps + 3), error),
+                );
+            }
+        }
     }
 
     fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
         // the required size depends on how many lap times the user want to diaplay
-        Vec2::new(20, self.show_laps + 1) // columns, rows (width, height)
+        let label_width = self
+            .lap_labels
+            .iter()
+            .flatten()
+            .map(|label| label.len() + 1)
+            .max()
+            .unwrap_or(0);
+        let mut width = 35 + label_width;
+        if self.target_lap.is_some() {
+            width = std::cmp::max(width, 45); // room for "vs. target"
+        }
+        match &self.prompt {
+            Some((_, input)) => Vec2::new(
+                std::cmp::max(width, 24 + input.len()),
+                self.show_laps + self.header_height() + 3, // the buttons, the prompt and possibly an error
+            ),
+            None => Vec2::new(width, self.show_laps + self.header_height() + 1), // columns, rows (width, height)
+        }
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
+        if event == Event::Refresh {
+            return self.check_target_lap();
+        }
+        if self.prompt.is_some() {
+            return self.on_prompt_event(event);
+        }
         match event {
             // pause/resume the stopwatch when pressing "Space"
             Event::Char(' ') => {
-                self.stopwatch.pause_or_resume();
+                self.pause_or_resume();
             }
             Event::Key(Key::Enter) => {
                 self.show_laps_offset = 0; // FUTURE: maybe unneeded?
                 return self.stop();
             }
             Event::Char('l') => {
-                self.stopwatch.lap();
-                self.show_laps_offset = 0;
+                self.lap();
+            }
+            // lap, then label the new lap
+            Event::Char('L') => {
+                self.lap();
+                self.edit_label();
+            }
+            // label the selected (or the latest) lap
+            Event::Char('n') => {
+                self.edit_label();
+            }
+            Event::Key(Key::Esc) => {
+                self.selected_lap = None;
+            }
+            Event::Char('u') => {
+                self.undo();
+            }
+            // delete the selected lap
+            Event::Char('x') | Event::Key(Key::Del) => {
+                self.delete_lap();
+            }
+            // merge the selected lap with the previous one
+            Event::Char('j') => {
+                self.merge_laps();
+            }
+            // split the selected (or the latest) lap
+            Event::Char('s') => {
+                if let Some(lap) = self.selected_or_latest_lap() {
+                    self.prompt = Some((Prompt::Split(lap), String::new()));
+                }
+            }
+            Event::Char('m') => {
+                self.lap_column = self.lap_column.next();
             }
             Event::Key(Key::Up) => {
-                self.decrement_show_lap_offset();
+                self.move_selection(1);
             }
             Event::Key(Key::Down) => {
-                self.increment_show_lap_offset();
+                self.move_selection(-1);
             }
-            Event::Mouse { event, .. } => {
+            Event::Mouse {
+                offset,
+                position,
+                event,
+            } => {
                 match event {
+                    MouseEvent::Press(MouseButton::Left) => {
+                        let position = position.checked_sub(offset);
+                        let buttons_row = self.header_height() + self.show_laps;
+                        match position {
+                            Some(p) if p.y == buttons_row => {
+                                let buttons = self.buttons();
+                                if let Some(i) = button_at(buttons, p.x) {
+                                    return self.press_button(buttons[i]);
+                                }
+                            }
+                            _ => {
+                                self.selected_lap = position.and_then(|p| self.lap_at_row(p.y));
+                            }
+                        }
+                    }
                     MouseEvent::WheelUp => {
                         self.decrement_show_lap_offset();
                     }
@@ -146,3 +786,61 @@ impl View for StopwatchView {
         EventResult::Consumed(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A running stopwatch with `n` laps
+    fn stopwatch(n: usize) -> StopwatchView {
+        let mut view = StopwatchView::new().running();
+        for _ in 0..n {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            view.lap();
+        }
+        view
+    }
+
+    fn assert_aligned(view: &StopwatchView, n: usize) {
+        let data = &view.stopwatch.data;
+        assert_eq!(data.laps.len(), n);
+        assert_eq!(data.lap_moments.len(), n);
+        assert_eq!(view.lap_labels.len(), n);
+        assert!(data.lap_moments.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn undo_lap() {
+        let mut view = stopwatch(3);
+        let moments = view.stopwatch.data.lap_moments.clone();
+        view.undo();
+        assert_aligned(&view, 2);
+        assert_eq!(view.stopwatch.data.lap_moments, moments[..2]);
+    }
+
+    #[test]
+    fn edits_keep_the_lap_moments() {
+        let mut view = stopwatch(4);
+        let moments = view.stopwatch.data.lap_moments.clone();
+
+        view.selected_lap = Some(1);
+        view.delete_lap();
+        assert_aligned(&view, 3);
+        view.selected_lap = Some(1);
+        view.merge_laps();
+        assert_aligned(&view, 2);
+        assert_eq!(
+            view.stopwatch.data.lap_moments,
+            vec![moments[2], moments[3]]
+        );
+        let first = view.stopwatch.data.laps[1] / 2;
+        view.split_lap(1, first).unwrap();
+        assert_aligned(&view, 3);
+
+        view.undo();
+        view.undo();
+        view.undo();
+        assert_aligned(&view, 4);
+        assert_eq!(view.stopwatch.data.lap_moments, moments);
+    }
+}
@@ -19,22 +19,118 @@
 //!
 //! ## Expected Behavior
 //!
-//! On start, the user sets the expected duration (HH:MM:SS). Left/right arrow keys and the TAB key move the focus. A list of frequently/recently used durations is provided. Press "Enter" to start.
+//! On start, the user sets the expected duration (days and HH:MM:SS). Left/right arrow keys, TAB/Shift+TAB and Home/End move the focus. Up/down arrow keys (or "k"/"j") increment/decrement the focused field, carrying over to the next field (and wrapping around at zero and at the longest duration), and PageUp/PageDown change it by 10. Backspace deletes the last digit. A list of frequently/recently used durations is provided. Press "Enter" to start.
 //!
-//! When the timer is running, press "Space" to pause/resume and "Enter" to cancel.
+//! Alternatively, press "e" to type the duration, e.g. "1h30m", "90s", "2.5 min" or "until 15:00". The parsed duration (or what is wrong with the input) is shown below as you type. Press "Enter" to start, or "Esc" to go back to the fields.
 //!
-//! When the timer finishes (when counting to 00:00:00 or cancelled), the callback set with `on_finish()` is called.
+//! When the timer is running, press "Space" to pause/resume, "+"/"-" to add/subtract a minute and "Enter" to cancel. With `with_progress_bar()`, a progress bar as wide as the terminal is shown below the remaining time, along with the percentage done, the elapsed and total time, and when the timer will end. The progress bar and the remaining time turn yellow, then red, as the color thresholds are reached. With `with_ring()`, an `AnalogClockView` countdown ring is shown below them, shrinking anticlockwise towards 12 o'clock as the time runs out.
+//!
+//! Warning thresholds (`with_warnings()`) make the remaining time flash and call the callback set with `on_warning()` when they are crossed. Adding time re-arms the thresholds which are no longer reached, and when several are crossed at once (e.g. by subtracting time), each of them warns, in the order they were reached.
+//!
+//! With a mouse, click a field to focus it, and scroll to increment or decrement it (carrying over to the next field, e.g. 00:59 + 1 minute is 01:00). The buttons below the timer start, pause/resume and cancel it.
+//!
+//! When the timer finishes (when counting to 00:00:00 or cancelled), the callback set with `on_finish()` is called. Then press "z" to snooze (run again for the snooze length, 5 minutes by default), "r" to repeat the same duration, or "Space"/"Enter" to go back to the config screen. With `with_auto_repeat()`, a timer which counts to 00:00:00 starts again right away, N times or forever; `on_finish()` is called after each run.
 
-use chrono::Duration;
+use super::buttons::{button_at, buttons_width, draw_buttons};
+use super::AnalogClockView;
+use crate::utils::{dhhmmss, parse_timer_duration};
+use chrono::{DateTime, Duration, Local};
 use clock_core::timer::{Timer, TimerData};
 use cursive::{
-    event::{Callback, Event, EventResult, Key},
-    theme::ColorStyle,
+    event::{Callback, Event, EventResult, Key, MouseButton, MouseEvent},
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
     view::View,
     Cursive, Printer, Vec2, With,
 };
 use hhmmss::Hhmmss;
+use std::fmt;
 use std::rc::Rc;
+use std::str::FromStr;
+
+/// The longest duration which can be set is 999 days, 23:59:59
+const MAX_DAYS: u32 = 999;
+/// The height of the countdown ring, which is twice as wide
+const RING_ROWS: usize = 10;
+
+/// A point shortly before the timer finishes: when a percentage of the duration is left, or a
+/// fixed amount of time, e.g. `10%` or `1m`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Threshold {
+    Percent(f64),
+    Remaining(Duration),
+}
+
+impl Threshold {
+    /// The remaining time at which the threshold is reached, for a timer of `total`
+    pub fn remaining_at(self, total: Duration) -> Duration {
+        match self {
+            Threshold::Percent(percent) => {
+                Duration::milliseconds((total.num_milliseconds() as f64 * percent / 100.0) as i64)
+            }
+            Threshold::Remaining(time) => time,
+        }
+    }
+
+    pub fn is_reached(self, remaining: Duration, total: Duration) -> bool {
+        remaining <= self.remaining_at(total)
+    }
+}
+
+/// How many times a timer runs with `TimerView::with_auto_repeat`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Repeat {
+    Times(u32),
+    Forever,
+}
+
+impl FromStr for Repeat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "forever" => Ok(Repeat::Forever),
+            _ => match s.parse() {
+                Ok(n) if n > 0 => Ok(Repeat::Times(n)),
+                _ => Err(format!("expected a positive number or \"forever\": {}", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Threshold::Percent(percent) => write!(f, "{}%", percent),
+            Threshold::Remaining(time) => match time.to_std() {
+                Ok(time) => write!(f, "{}", humantime::format_duration(time)),
+                Err(_) => write!(f, "{}", time),
+            },
+        }
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(Threshold::Percent(percent)),
+                _ => Err(format!("invalid percentage: {}", s)),
+            },
+            None => {
+                let duration: std::time::Duration = s
+                    .parse::<humantime::Duration>()
+                    .map_err(|e| format!("{}: {}", s, e))?
+                    .into();
+                Duration::from_std(duration)
+                    .map(Threshold::Remaining)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
 
 #[derive(Copy, Clone)]
 enum TimerViewState {
@@ -44,165 +140,649 @@ enum TimerViewState {
 }
 
 struct TimerViewConfig {
-    h: u8,
-    m: u8,
-    s: u8,
-    focus: u8, // match focus % 3 {0 => h, 1 => m, 2 => s}
-    input_buffer: Vec<u8>,
+    d: u32,
+    h: u32,
+    m: u32,
+    s: u32,
+    focus: u8, // match focus {0 => d, 1 => h, 2 => m, 3 => s}
+    input_buffer: Vec<u32>,
+    /// The text typed in the free-form entry, if it is open
+    entry: Option<String>,
 }
 
+/// Called with the data of the timer when it finishes, and whether it was cancelled
+type OnFinish = Rc<dyn Fn(&mut Cursive, TimerData, bool)>;
+
+/// Called with each warning threshold which is crossed
+type OnWarning = Rc<dyn Fn(&mut Cursive, Threshold)>;
+
 pub struct TimerView {
     timer: Timer,
     remaining: Duration,
     state: TimerViewState,
     config: TimerViewConfig,
-    on_finish: Option<Rc<dyn Fn(&mut Cursive, TimerData)>>,
+    paused: bool,
+    /// The duration set on the config screen, which "r" and auto-repeat run again
+    duration: Duration,
+    /// The duration the timer was started with
+    total: Duration,
+    snooze: Duration,
+    auto_repeat: Option<Repeat>,
+    /// The number of the current run, counting from 1, when auto-repeating
+    run: u32,
+    progress_bar: bool,
+    ring: bool,
+    color_thresholds: Vec<Threshold>,
+    warnings: Vec<Threshold>,
+    /// Whether each warning threshold has been reached
+    warned: Vec<bool>,
+    /// Time added (or subtracted) with "+"/"-" while running
+    adjustment: Duration,
+    /// When the latest warning started flashing
+    flash_start: Option<DateTime<Local>>,
+    on_warning: Option<OnWarning>,
+    on_finish: Option<OnFinish>,
 }
 
 impl TimerView {
-    pub fn new(h: u8, m: u8, s: u8) -> Self {
+    /// The longest duration the timer can be set to, 999 days, 23:59:59
+    pub fn max_duration() -> Duration {
+        Duration::days(MAX_DAYS as i64 + 1) - Duration::seconds(1)
+    }
+
+    /// Parses a duration the timer can be set to (see `parse_timer_duration`), as typed on the
+    /// command line or in the free-form entry
+    pub fn parse_duration(s: &str, now: DateTime<Local>) -> Result<Duration, String> {
+        let duration = parse_timer_duration(s, now)?;
+        if duration <= Duration::zero() {
+            Err(String::from("the duration must be longer than zero"))
+        } else if duration > Self::max_duration() {
+            Err(format!(
+                "the duration must be at most {}",
+                dhhmmss(Self::max_duration())
+            ))
+        } else {
+            Ok(duration)
+        }
+    }
+
+    pub fn new(duration: Duration) -> Self {
         let config = TimerViewConfig {
-            h,
-            m,
-            s,
-            focus: 1,
+            d: 0,
+            h: 0,
+            m: 0,
+            s: 0,
+            focus: 2,
             input_buffer: Vec::new(),
+            entry: None,
         };
         Self {
             timer: Timer::new(Duration::zero()),
             remaining: Duration::zero(),
             config,
             state: TimerViewState::Config,
+            paused: false,
+            duration: Duration::zero(),
+            total: Duration::zero(),
+            snooze: Duration::minutes(5),
+            auto_repeat: None,
+            run: 1,
+            progress_bar: false,
+            ring: false,
+            color_thresholds: vec![
+                Threshold::Percent(10.0),
+                Threshold::Remaining(Duration::minutes(1)),
+            ],
+            warnings: Vec::new(),
+            warned: Vec::new(),
+            adjustment: Duration::zero(),
+            flash_start: None,
+            on_warning: None,
             on_finish: None,
         }
+        .with(|v| v.set_duration(duration))
+    }
+
+    /// Sets the fields of the config screen
+    fn set_duration(&mut self, duration: Duration) {
+        let seconds = duration.num_seconds().max(0);
+        self.config.d = (seconds / 86400).min(MAX_DAYS as i64) as u32;
+        self.config.h = (seconds % 86400 / 3600) as u32;
+        self.config.m = (seconds % 3600 / 60) as u32;
+        self.config.s = (seconds % 60) as u32;
+    }
+
+    /// The duration set in the fields of the config screen, in seconds
+    fn config_seconds(&self) -> i64 {
+        self.config.d as i64 * 86400
+            + self.config.h as i64 * 3600
+            + self.config.m as i64 * 60
+            + self.config.s as i64
     }
 
     pub fn start(&mut self) {
-        let seconds =
-            self.config.h as i64 * 3600 + self.config.m as i64 * 60 + self.config.s as i64;
-        self.timer = Timer::new(Duration::seconds(seconds));
+        self.duration = Duration::seconds(self.config_seconds());
+        self.run = 1;
+        self.start_with(self.duration);
+    }
+
+    /// Sets how long "z" snoozes a finished timer for. Defaults to 5 minutes.
+    pub fn with_snooze(mut self, snooze: Duration) -> Self {
+        self.snooze = snooze;
+        self
+    }
+
+    /// Starts the timer again whenever it counts to 00:00:00, until it has run `repeat` times
+    pub fn with_auto_repeat(mut self, repeat: Repeat) -> Self {
+        self.auto_repeat = Some(repeat);
+        self
+    }
+
+    /// Shows a progress bar, the percentage done, the elapsed and total time and when the timer
+    /// will end
+    pub fn with_progress_bar(mut self) -> Self {
+        self.progress_bar = true;
+        self
+    }
+
+    /// Shows a ring below the remaining time, which shrinks from 12 o'clock as the time runs out
+    pub fn with_ring(mut self) -> Self {
+        self.ring = true;
+        self
+    }
+
+    /// The remaining time and the progress bar turn yellow when one of the thresholds is reached,
+    /// and red when two are reached. Defaults to the last 10% and the last minute.
+    pub fn with_color_thresholds(mut self, thresholds: Vec<Threshold>) -> Self {
+        self.color_thresholds = thresholds;
+        self
+    }
+
+    /// Warns when the remaining time reaches each of the thresholds, e.g. 5 minutes and 1 minute
+    /// left
+    pub fn with_warnings(mut self, thresholds: Vec<Threshold>) -> Self {
+        self.warnings = thresholds;
+        self
+    }
+
+    /// Sets a callback to be used when a warning threshold is crossed
+    pub fn set_on_warning<F, R>(&mut self, cb: F)
+    where
+        F: 'static + Fn(&mut Cursive, Threshold) -> R,
+    {
+        self.on_warning = Some(Rc::new(move |s, t| {
+            cb(s, t);
+        }));
+    }
+
+    pub fn on_warning<F, R>(self, cb: F) -> Self
+    where
+        F: 'static + Fn(&mut Cursive, Threshold) -> R,
+    {
+        self.with(|s| s.set_on_warning(cb))
+    }
+
+    fn start_with(&mut self, duration: Duration) {
+        self.timer = Timer::new(duration);
+        self.total = duration;
+        self.remaining = duration;
+        self.adjustment = Duration::zero();
+        self.flash_start = None;
+        // thresholds which are already reached at the start (e.g. 5 minutes left of a 3 minute
+        // timer) do not warn
+        self.warned = self
+            .warnings
+            .iter()
+            .map(|t| t.is_reached(duration, duration))
+            .collect();
         self.state = TimerViewState::Running;
+        self.paused = false;
         self.timer.pause_or_resume();
     }
 
+    fn pause_or_resume(&mut self) {
+        self.timer.pause_or_resume();
+        self.paused = !self.paused;
+    }
+
+    /// Pauses or resumes the timer while it is running. A timer which has not started or has
+    /// finished is left as it is.
+    pub fn set_paused(&mut self, paused: bool) {
+        if matches!(self.state, TimerViewState::Running) && self.paused != paused {
+            self.pause_or_resume();
+        }
+    }
+
+    /// Adds `delta` (which may be negative) to the remaining and the total time
+    fn adjust(&mut self, delta: Duration) {
+        let delta = delta.max(-self.remaining);
+        self.adjustment += delta;
+        self.total += delta;
+        self.remaining = self.timer.read() + self.adjustment;
+    }
+
+    /// Marks the warning thresholds which have been crossed, and returns all of them, in the order
+    /// they were reached. Thresholds which are no longer reached (after adding time) can warn again.
+    fn check_warnings(&mut self) -> Vec<Threshold> {
+        let mut crossed = Vec::new();
+        let total = self.total;
+        for (&threshold, warned) in self.warnings.iter().zip(self.warned.iter_mut()) {
+            let reached = threshold.is_reached(self.remaining, total);
+            if reached && !*warned {
+                crossed.push(threshold);
+            }
+            *warned = reached;
+        }
+        crossed.sort_by_key(|t| -t.remaining_at(total));
+        crossed
+    }
+
+    /// Flashes the remaining time, and calls the callback for each of the `thresholds`
+    fn warn(&mut self, thresholds: Vec<Threshold>) -> EventResult {
+        self.flash_start = Some(Local::now());
+        match self.on_warning.clone() {
+            Some(cb) => EventResult::Consumed(Some(Callback::from_fn_once(move |s| {
+                for threshold in thresholds {
+                    cb(s, threshold);
+                }
+            }))),
+            None => EventResult::Consumed(None),
+        }
+    }
+
+    /// Whether the remaining time is shown inverted, which it is every other 250ms for 2 seconds
+    /// after a warning
+    fn flashing(&self) -> bool {
+        match self.flash_start {
+            Some(start) => {
+                let ms = (Local::now() - start).num_milliseconds();
+                ms < 2000 && ms / 250 % 2 == 0
+            }
+            None => false,
+        }
+    }
+
     /// Sets a callback to be used when `<Enter>` is pressed or counting to 00:00:00
     ///
-    /// The elapsed time will be given to the callback.
+    /// The elapsed time, and whether the timer was cancelled, will be given to the callback.
     ///
     /// See also cursive::views::select_view::SelectView::set_on_submit
     pub fn set_on_finish<F, R>(&mut self, cb: F)
     where
-        F: 'static + Fn(&mut Cursive, TimerData) -> R,
+        F: 'static + Fn(&mut Cursive, TimerData, bool) -> R,
     {
-        self.on_finish = Some(Rc::new(move |s, t| {
-            cb(s, t);
+        self.on_finish = Some(Rc::new(move |s, t, cancelled| {
+            cb(s, t, cancelled);
         }));
     }
 
     pub fn on_finish<F, R>(self, cb: F) -> Self
     where
-        F: 'static + Fn(&mut Cursive, TimerData) -> R,
+        F: 'static + Fn(&mut Cursive, TimerData, bool) -> R,
     {
         self.with(|s| s.set_on_finish(cb))
     }
 
-    fn finish(&mut self) -> EventResult {
+    /// Stops the timer, which has counted to 00:00:00 unless it is `cancelled`, and starts it
+    /// again if it auto-repeats
+    fn finish(&mut self, cancelled: bool) -> EventResult {
         self.state = TimerViewState::Finished;
-        let data = self.timer.stop();
+        // `Timer::stop()` leaves the remaining time as it was when the timer was last paused
+        self.remaining = (self.timer.read() + self.adjustment).max(Duration::zero());
+        let mut data = self.timer.stop();
+        data.remaining = self.remaining;
+        let again = match self.auto_repeat {
+            _ if cancelled => false,
+            Some(Repeat::Times(n)) => self.run < n,
+            Some(Repeat::Forever) => true,
+            None => false,
+        };
+        if again {
+            self.run += 1;
+            self.start_with(self.duration);
+        }
         if self.on_finish.is_some() {
             let cb = self.on_finish.clone().unwrap();
-            EventResult::Consumed(Some(Callback::from_fn_once(move |s| cb(s, data))))
+            EventResult::Consumed(Some(Callback::from_fn_once(move |s| {
+                cb(s, data, cancelled)
+            })))
         } else {
             EventResult::Consumed(None)
         }
     }
 
     fn draw_running(&self, printer: &Printer) {
-        printer.print((0, 0), &self.remaining.hhmmssxxx());
+        let reached = self
+            .color_thresholds
+            .iter()
+            .filter(|t| t.is_reached(self.remaining, self.total))
+            .count();
+        let color = match reached {
+            _ if self.flashing() => ColorStyle::highlight(),
+            0 => ColorStyle::primary(),
+            1 => ColorStyle::new(Color::Dark(BaseColor::Yellow), PaletteColor::View),
+            _ => ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View),
+        };
+        printer.with_color(color, |printer| {
+            printer.print((0, 0), &dhhmmssxxx(self.remaining))
+        });
+        match self.auto_repeat {
+            Some(Repeat::Times(n)) => printer.print((19, 0), &format!("run {} of {}", self.run, n)),
+            Some(Repeat::Forever) => printer.print((19, 0), &format!("run {}", self.run)),
+            None => {}
+        }
+        let elapsed = self.total - self.remaining;
+        let progress = if self.total > Duration::zero() {
+            elapsed.num_milliseconds() as f64 / self.total.num_milliseconds() as f64
+        } else {
+            1.0
+        };
+        if self.ring {
+            let top = if self.progress_bar { 3 } else { 1 };
+            let ring = AnalogClockView::ring(1.0 - progress);
+            printer.with_color(color, |printer| {
+                ring.draw(
+                    &printer
+                        .offset((0, top))
+                        .cropped((printer.size.x, RING_ROWS)),
+                )
+            });
+        }
+        if !self.progress_bar {
+            return;
+        }
+
+        let width = printer.size.x;
+        let filled = ((width as f64 * progress).round() as usize).min(width);
+        printer.with_color(color, |printer| {
+            printer.print((0, 1), &"█".repeat(filled));
+        });
+        printer.with_color(ColorStyle::secondary(), |printer| {
+            printer.print((filled, 1), &"░".repeat(width - filled));
+        });
+
+        let now = Local::now();
+        let end = now + self.remaining;
+        let format = if end.date_naive() == now.date_naive() {
+            "%H:%M"
+        } else {
+            "%Y-%m-%d %a %H:%M"
+        };
+        let mut stats = format!(
+            "{:>3.0}%  {} / {}  ",
+            progress * 100.0,
+            dhhmmss(elapsed),
+            dhhmmss(self.total)
+        );
+        if self.paused {
+            stats.push_str("paused");
+        } else {
+            stats.push_str(&format!("ends at {}", end.format(format)));
+        }
+        printer.print((0, 2), &stats);
+    }
+
+    /// The labels of the buttons below the timer
+    fn buttons(&self) -> &'static [&'static str] {
+        match self.state {
+            TimerViewState::Config if self.config.entry.is_some() => &["Start", "Back"],
+            TimerViewState::Config => &["Start"],
+            TimerViewState::Running if self.paused => &["Resume", "Cancel"],
+            TimerViewState::Running => &["Pause", "Cancel"],
+            TimerViewState::Finished => &["Snooze", "Repeat", "OK"],
+        }
+    }
+
+    /// The row of the buttons, below the fields, the free-form entry or the remaining time
+    fn buttons_row(&self) -> usize {
+        match self.state {
+            TimerViewState::Config if self.config.entry.is_some() => 2,
+            TimerViewState::Running => {
+                let progress_rows = if self.progress_bar { 3 } else { 1 };
+                let ring_rows = if self.ring { RING_ROWS } else { 0 };
+                progress_rows + ring_rows
+            }
+            _ => 1,
+        }
+    }
+
+    fn press_button(&mut self, label: &str) -> EventResult {
+        match label {
+            "Start" if self.config.entry.is_some() => self.submit_entry(),
+            "Start" => self.start(),
+            "Back" => self.config.entry = None,
+            "Pause" | "Resume" => self.pause_or_resume(),
+            "Cancel" => return self.finish(true),
+            "Snooze" => self.start_with(self.snooze),
+            "Repeat" => self.start_with(self.duration),
+            "OK" => self.state = TimerViewState::Config,
+            _ => unreachable!(),
+        }
+        EventResult::Consumed(None)
+    }
+
+    fn on_mouse_event(&mut self, position: Vec2, event: MouseEvent) -> EventResult {
+        let on_fields = position.y == 0
+            && matches!(self.state, TimerViewState::Config)
+            && self.config.entry.is_none();
+        match event {
+            MouseEvent::Press(MouseButton::Left) if position.y == self.buttons_row() => {
+                let buttons = self.buttons();
+                match button_at(buttons, position.x) {
+                    Some(i) => return self.press_button(buttons[i]),
+                    None => return EventResult::Ignored,
+                }
+            }
+            MouseEvent::Press(MouseButton::Left) if on_fields => match field_at(position.x) {
+                Some(field) => {
+                    self.config.focus = field;
+                    self.config.input_buffer.clear();
+                }
+                None => return EventResult::Ignored,
+            },
+            MouseEvent::WheelUp | MouseEvent::WheelDown if on_fields => {
+                // scrolling over a field focuses it
+                if let Some(field) = field_at(position.x) {
+                    self.config.focus = field;
+                }
+                let delta = if event == MouseEvent::WheelUp { 1 } else { -1 };
+                self.adjust_selection(delta);
+            }
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
     }
 
     fn draw_finished(&self, printer: &Printer) {
         printer.print((0, 0), "FINISHED!");
     }
 
-    fn draw_config(&self, printer: &Printer) {
-        fn format(n: u8) -> String {
-            format!("{:02}", n)
+    /// Whether the remaining time is followed by the number of the run
+    fn shows_run(&self) -> bool {
+        matches!(self.state, TimerViewState::Running) && self.auto_repeat.is_some()
+    }
+
+    fn draw_entry(&self, printer: &Printer, entry: &str) {
+        printer.print((0, 0), &format!("> {}", entry));
+        printer.with_color(ColorStyle::highlight(), |printer| {
+            printer.print((entry.chars().count() + 2, 0), " ")
+        });
+        match entry_preview(entry) {
+            _ if entry.trim().is_empty() => {
+                printer.with_color(ColorStyle::secondary(), |printer| {
+                    printer.print((0, 1), "e.g. 1h30m, 2.5 min, until 15:00")
+                });
+            }
+            Ok(preview) => printer.print((0, 1), &preview),
+            Err(e) => {
+                let error = ColorStyle::new(Color::Dark(BaseColor::Red), PaletteColor::View);
+                printer.with_color(error, |printer| printer.print((0, 1), &e));
+            }
         }
-        let (h, m, s) = (
-            format(self.config.h),
-            format(self.config.m),
-            format(self.config.s),
-        );
+    }
 
-        if self.config.focus % 3 == 0 {
-            printer.with_color(ColorStyle::highlight(), |printer| printer.print((0, 0), &h));
-        } else {
-            printer.print((0, 0), &h);
+    /// Starts the timer with the duration typed in the free-form entry, unless it is invalid
+    fn submit_entry(&mut self) {
+        let entry = self.config.entry.clone().unwrap_or_default();
+        if let Ok(duration) = Self::parse_duration(&entry, Local::now()) {
+            self.config.entry = None;
+            self.set_duration(duration);
+            self.duration = duration;
+            self.run = 1;
+            self.start_with(duration);
         }
-        printer.print((2, 0), ":");
-        if self.config.focus % 3 == 1 {
-            printer.with_color(ColorStyle::highlight(), |printer| printer.print((3, 0), &m));
-        } else {
-            printer.print((3, 0), &m);
+    }
+
+    fn draw_config(&self, printer: &Printer) {
+        if let Some(entry) = &self.config.entry {
+            return self.draw_entry(printer, entry);
         }
-        printer.print((5, 0), ":");
-        if self.config.focus % 3 == 2 {
-            printer.with_color(ColorStyle::highlight(), |printer| printer.print((6, 0), &s));
-        } else {
-            printer.print((6, 0), &s);
+        let fields = [
+            (0, format!("{:>3}", self.config.d)),
+            (5, format!("{:02}", self.config.h)),
+            (8, format!("{:02}", self.config.m)),
+            (11, format!("{:02}", self.config.s)),
+        ];
+        for (i, (x, text)) in fields.iter().enumerate() {
+            if self.config.focus == i as u8 {
+                printer.with_color(ColorStyle::highlight(), |printer| {
+                    printer.print((*x, 0), text)
+                });
+            } else {
+                printer.print((*x, 0), text);
+            }
         }
+        printer.print((3, 0), "d");
+        printer.print((7, 0), ":");
+        printer.print((10, 0), ":");
     }
 
-    // fn get_selection(&self) -> u8 {
-    //     match self.config.focus % 3 {
-    //         0 => self.config.h,
-    //         1 => self.config.m,
-    //         2 => self.config.s,
-    //         _ => unreachable!(),
-    //     }
-    // }
-
-    fn set_selection(&mut self, v: u8) {
-        match self.config.focus % 3 {
-            0 => self.config.h = v,
-            1 => self.config.m = v,
-            2 => self.config.s = v,
+    /// Adds `delta` units of the focused field, carrying over to (or borrowing from) the other
+    /// fields, e.g. 00:59:00 + 1 minute is 01:00:00. Going below zero wraps around to the longest
+    /// duration, and going above it wraps around to zero.
+    fn adjust_selection(&mut self, delta: i64) {
+        let unit = match self.config.focus {
+            0 => 86400,
+            1 => 3600,
+            2 => 60,
+            3 => 1,
+            _ => unreachable!(),
+        };
+        let max = MAX_DAYS as i64 * 86400 + 86399;
+        let seconds = (self.config_seconds() + delta * unit).rem_euclid(max + 1);
+        self.set_duration(Duration::seconds(seconds));
+        self.config.input_buffer.clear();
+    }
+
+    fn set_selection(&mut self, v: u32) {
+        match self.config.focus {
+            0 => self.config.d = v,
+            1 => self.config.h = v,
+            2 => self.config.m = v,
+            3 => self.config.s = v,
             _ => unreachable!(),
         }
     }
 
     fn move_focus_right(&mut self) {
-        self.config.focus += 1;
-        self.config.input_buffer.clear();
+        self.move_focus_to((self.config.focus + 1) % 4);
     }
 
     fn move_focus_left(&mut self) {
-        self.config.focus -= 1;
+        self.move_focus_to((self.config.focus + 3) % 4);
+    }
+
+    fn move_focus_to(&mut self, field: u8) {
+        self.config.focus = field;
         self.config.input_buffer.clear();
     }
 
-    fn read_buffer(&self) -> u8 {
-        let buffer = &self.config.input_buffer;
-        let n = match buffer.len() {
-            0 => 0,
-            1 => buffer[0],
-            2 => buffer[0] * 10 + buffer[1],
-            _ => unreachable!(),
-        };
-        match self.config.focus % 3 {
-            0 => n,
-            1 | 2 => {
-                if n < 60 {
-                    n
-                } else {
-                    59
-                }
+    /// Deletes the last digit typed in the focused field, or of its value if nothing was typed
+    fn backspace(&mut self) {
+        if self.config.input_buffer.is_empty() {
+            let value = match self.config.focus {
+                0 => self.config.d,
+                1 => self.config.h,
+                2 => self.config.m,
+                3 => self.config.s,
+                _ => unreachable!(),
+            };
+            if value == 0 {
+                return;
             }
-            _ => unreachable!(),
+            self.config.input_buffer = value
+                .to_string()
+                .chars()
+                .map(|c| c.to_digit(10).unwrap())
+                .collect();
         }
+        self.config.input_buffer.pop();
+        self.set_selection(self.read_buffer());
+    }
+
+    /// The number of digits of the focused field
+    fn field_width(&self) -> usize {
+        match self.config.focus {
+            0 => 3,
+            _ => 2,
+        }
+    }
+
+    fn read_buffer(&self) -> u32 {
+        let n = self
+            .config
+            .input_buffer
+            .iter()
+            .fold(0, |n, &digit| n * 10 + digit);
+        let max = match self.config.focus {
+            0 => MAX_DAYS,
+            1 => 23,
+            2 | 3 => 59,
+            _ => unreachable!(),
+        };
+        n.min(max)
+    }
+}
+
+/// The field of the config screen at column `x` (see `draw_config`)
+fn field_at(x: usize) -> Option<u8> {
+    match x {
+        0..=3 => Some(0),
+        5..=6 => Some(1),
+        8..=9 => Some(2),
+        11..=12 => Some(3),
+        _ => None,
+    }
+}
+
+/// The parsed duration of the free-form entry and when the timer would end, or what is wrong
+fn entry_preview(entry: &str) -> Result<String, String> {
+    let now = Local::now();
+    let duration = TimerView::parse_duration(entry, now)?;
+    let end = now + duration;
+    let format = if end.date_naive() == now.date_naive() {
+        "%H:%M:%S"
+    } else {
+        "%Y-%m-%d %a %H:%M:%S"
+    };
+    Ok(format!(
+        "= {}, ends at {}",
+        dhhmmss(duration),
+        end.format(format)
+    ))
+}
+
+/// Pretty-prints the remaining time in the form `3d 04:05:06.789`, or `04:05:06.789` when it is
+/// shorter than a day
+fn dhhmmssxxx(d: Duration) -> String {
+    let days = d.num_days();
+    if days > 0 {
+        format!("{}d {}", days, (d - Duration::days(days)).hhmmssxxx())
+    } else {
+        d.hhmmssxxx()
     }
 }
+
 impl View for TimerView {
     fn draw(&self, printer: &Printer) {
         match self.state {
@@ -210,35 +790,70 @@ impl View for TimerView {
             TimerViewState::Config => self.draw_config(printer),
             TimerViewState::Finished => self.draw_finished(printer),
         }
+        draw_buttons(printer, self.buttons_row(), self.buttons());
     }
 
-    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
-        // the required size depends on how many lap times the user want to diaplay
-        Vec2::new(12, 1) // columns, rows (width, height)
+    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        match (self.state, &self.config.entry) {
+            // room for the preview or the error message
+            (TimerViewState::Config, Some(entry)) => {
+                let preview = match entry_preview(entry) {
+                    Ok(preview) | Err(preview) => preview.chars().count(),
+                };
+                Vec2::new(preview.max(entry.chars().count() + 3).max(32), 3)
+            }
+            // the progress bar is as wide as possible
+            (TimerViewState::Running, _) if self.progress_bar => {
+                Vec2::new(constraint.x.max(40), self.buttons_row() + 1)
+            }
+            (TimerViewState::Running, _) if self.ring => {
+                let width = if self.shows_run() { 33 } else { RING_ROWS * 2 };
+                Vec2::new(width, self.buttons_row() + 1)
+            }
+            _ if self.shows_run() => Vec2::new(33, 2),
+            _ => Vec2::new(buttons_width(self.buttons()).max(17), 2), // columns, rows (width, height)
+        }
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
+        if let Event::Mouse {
+            offset,
+            position,
+            event,
+        } = event
+        {
+            return match position.checked_sub(offset) {
+                Some(position) => self.on_mouse_event(position, event),
+                None => EventResult::Ignored,
+            };
+        }
         match self.state {
             TimerViewState::Running => {
                 match event {
                     // pause/resume the timer when pressing "Space"
                     Event::Char(' ') => {
-                        self.timer.pause_or_resume();
+                        self.pause_or_resume();
                     }
+                    Event::Char('+') | Event::Char('=') => self.adjust(Duration::minutes(1)),
+                    Event::Char('-') => self.adjust(Duration::minutes(-1)),
                     Event::Refresh => {
-                        self.remaining = self.timer.read();
+                        self.remaining = self.timer.read() + self.adjustment;
                         if self.remaining.num_milliseconds() < 10 {
-                            return self.finish();
+                            return self.finish(false);
+                        }
+                        let crossed = self.check_warnings();
+                        if !crossed.is_empty() {
+                            return self.warn(crossed);
                         }
                     }
                     // calcel
                     Event::Key(Key::Enter) => {
-                        return self.finish();
+                        return self.finish(true);
                     }
                     _ => {
                         if self.timer.data.remaining.num_milliseconds() < 10 {
                             self.state = TimerViewState::Finished;
-                            return self.finish();
+                            return self.finish(false);
                         }
                     } //return EventResult::Ignored,
                 }
@@ -247,33 +862,48 @@ impl View for TimerView {
                 Event::Char(' ') | Event::Key(Key::Enter) => {
                     self.state = TimerViewState::Config;
                 }
+                Event::Char('z') => self.start_with(self.snooze),
+                Event::Char('r') => self.start_with(self.duration),
                 _ => return EventResult::Ignored,
             },
-            TimerViewState::Config => match event {
-                Event::Char(c) => {
-                    if c.is_numeric() {
-                        self.config.input_buffer.push(c.to_digit(10).unwrap() as u8);
+            TimerViewState::Config if self.config.entry.is_some() => {
+                let entry = self.config.entry.as_mut().unwrap();
+                match event {
+                    Event::Char(c) => entry.push(c),
+                    Event::Key(Key::Backspace) => {
+                        entry.pop();
                     }
+                    Event::Key(Key::Enter) => self.submit_entry(),
+                    Event::Key(Key::Esc) => self.config.entry = None,
+                    _ => return EventResult::Ignored,
+                }
+            }
+            TimerViewState::Config => match event {
+                Event::Char('e') => {
+                    self.config.entry = Some(String::new());
+                    self.config.input_buffer.clear();
+                }
+                Event::Char('k') | Event::Key(Key::Up) => self.adjust_selection(1),
+                Event::Char('j') | Event::Key(Key::Down) => self.adjust_selection(-1),
+                Event::Key(Key::PageUp) => self.adjust_selection(10),
+                Event::Key(Key::PageDown) => self.adjust_selection(-10),
+                Event::Key(Key::Backspace) => self.backspace(),
+                Event::Char(c) if c.is_ascii_digit() => {
+                    self.config.input_buffer.push(c.to_digit(10).unwrap());
                     self.set_selection(self.read_buffer());
-                    if self.config.input_buffer.len() == 2 {
+                    if self.config.input_buffer.len() == self.field_width() {
                         self.move_focus_right();
                     }
                 }
                 Event::Key(Key::Right) | Event::Key(Key::Tab) => {
                     self.move_focus_right();
                 }
-                Event::Key(Key::Left) => self.move_focus_left(),
+                Event::Key(Key::Left) | Event::Shift(Key::Tab) => self.move_focus_left(),
+                Event::Key(Key::Home) => self.move_focus_to(0),
+                Event::Key(Key::End) => self.move_focus_to(3),
                 Event::Key(Key::Enter) => {
                     self.start();
                 }
-                // Event::Mouse {
-                //     offset,
-                //     position,
-                //     event,
-                // } => match event {
-                //     MouseEvent::WheelDown => self.set_selection(self.get_selection() - 1),
-                //     MouseEvent::WheelUp => self.set_selection(self.get_selection() + 1),
-                // },
                 _ => return EventResult::Ignored,
             },
         }
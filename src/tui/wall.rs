@@ -0,0 +1,211 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Wall Clock TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows the current local time in large digits, scaled to fill the available area, with the date
+//! below it and optionally an analog face above it. Press "t" to switch between 12 and 24 hours,
+//! "s" to show or hide the seconds, and "a" to show or hide the analog face.
+
+use super::AnalogClockView;
+use chrono::{DateTime, Local, Timelike};
+use cursive::{
+    direction::Direction,
+    event::{Event, EventResult},
+    theme::ColorStyle,
+    view::View,
+    Printer, Vec2,
+};
+
+/// Glyphs of the large digits, 3 pixels wide and 5 high
+const DIGITS: [[&str; 5]; 10] = [
+    ["###", "# #", "# #", "# #", "###"],
+    ["  #", "  #", "  #", "  #", "  #"],
+    ["###", "  #", "###", "#  ", "###"],
+    ["###", "  #", "###", "  #", "###"],
+    ["# #", "# #", "###", "  #", "  #"],
+    ["###", "#  ", "###", "  #", "###"],
+    ["###", "#  ", "###", "# #", "###"],
+    ["###", "  #", "  #", "  #", "  #"],
+    ["###", "# #", "###", "# #", "###"],
+    ["###", "# #", "###", "  #", "###"],
+];
+const COLON: [&str; 5] = [" ", "#", " ", "#", " "];
+const BLANK: [&str; 5] = ["   "; 5];
+const GLYPH_HEIGHT: usize = 5;
+
+pub struct WallClockView {
+    twelve_hour: bool,
+    seconds: bool,
+    /// `strftime`-like format of the date, or `None` to hide it
+    date_format: Option<String>,
+    analog: bool,
+    face: AnalogClockView,
+}
+
+impl Default for WallClockView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WallClockView {
+    /// A 24-hour clock with seconds and the date, e.g. "Monday, 2 November 2020"
+    pub fn new() -> Self {
+        Self {
+            twelve_hour: false,
+            seconds: true,
+            date_format: Some("%A, %-d %B %Y".to_owned()),
+            analog: false,
+            face: AnalogClockView::new(),
+        }
+    }
+
+    pub fn with_12_hour(mut self) -> Self {
+        self.twelve_hour = true;
+        self
+    }
+
+    pub fn without_seconds(mut self) -> Self {
+        self.seconds = false;
+        self.face.set_seconds(false);
+        self
+    }
+
+    /// Sets the format of the date (see `chrono::format::strftime`), or hides it if `None`
+    pub fn with_date_format(mut self, format: Option<String>) -> Self {
+        self.date_format = format;
+        self
+    }
+
+    /// Shows an analog face above the digits
+    pub fn with_analog_face(mut self) -> Self {
+        self.analog = true;
+        self
+    }
+
+    /// The glyphs of the time, e.g. "14:05:09", or " 2:05:09" and "PM" in 12-hour mode
+    fn glyphs(&self, now: &DateTime<Local>) -> (Vec<[&'static str; 5]>, Option<&'static str>) {
+        let (hour, suffix) = if self.twelve_hour {
+            let (pm, hour) = now.hour12();
+            (hour, Some(if pm { "PM" } else { "AM" }))
+        } else {
+            (now.hour(), None)
+        };
+        let mut glyphs = vec![
+            if hour < 10 && self.twelve_hour {
+                BLANK
+            } else {
+                DIGITS[hour as usize / 10]
+            },
+            DIGITS[hour as usize % 10],
+            COLON,
+            DIGITS[now.minute() as usize / 10],
+            DIGITS[now.minute() as usize % 10],
+        ];
+        if self.seconds {
+            glyphs.push(COLON);
+            glyphs.push(DIGITS[now.second() as usize / 10]);
+            glyphs.push(DIGITS[now.second() as usize % 10]);
+        }
+        (glyphs, suffix)
+    }
+
+    /// Draws the glyphs with each pixel `scale` rows high and twice as many columns wide
+    fn draw_digits(&self, printer: &Printer, glyphs: &[[&str; 5]], origin: Vec2, scale: usize) {
+        let mut x = origin.x;
+        for glyph in glyphs {
+            let width = glyph[0].len();
+            for (row, line) in glyph.iter().enumerate() {
+                for (col, pixel) in line.chars().enumerate() {
+                    if pixel == '#' {
+                        let block = "█".repeat(2 * scale);
+                        for dy in 0..scale {
+                            printer
+                                .print((x + col * 2 * scale, origin.y + row * scale + dy), &block);
+                        }
+                    }
+                }
+            }
+            x += (width + 1) * 2 * scale;
+        }
+    }
+}
+
+impl View for WallClockView {
+    fn draw(&self, printer: &Printer) {
+        let now = Local::now();
+        let (glyphs, suffix) = self.glyphs(&now);
+        let size = printer.size;
+        let date = self.date_format.as_ref().map(|f| now.format(f).to_string());
+
+        // width of the digits in pixels, and the rows needed besides the digits
+        let pixels = glyphs.iter().map(|g| g[0].len() + 1).sum::<usize>() - 1;
+        let text_rows = if date.is_some() { 2 } else { 0 };
+        let face_rows = if self.analog { size.y / 2 } else { 0 };
+        let suffix_width = if suffix.is_some() { 3 } else { 0 };
+        let scale = ((size.x.saturating_sub(suffix_width)) / (pixels * 2))
+            .min(size.y.saturating_sub(text_rows + face_rows) / GLYPH_HEIGHT)
+            .max(1);
+
+        let digits_width = pixels * 2 * scale + suffix_width;
+        let digits_height = GLYPH_HEIGHT * scale;
+        let total_height = face_rows + digits_height + text_rows;
+        let top = size.y.saturating_sub(total_height) / 2;
+
+        if self.analog && face_rows >= 3 {
+            self.face
+                .draw(&printer.offset((0, top)).cropped((size.x, face_rows)));
+        }
+        let origin = Vec2::new(size.x.saturating_sub(digits_width) / 2, top + face_rows);
+        self.draw_digits(printer, &glyphs, origin, scale);
+        if let Some(suffix) = suffix {
+            printer.print((origin.x + pixels * 2 * scale + 1, origin.y), suffix);
+        }
+        if let Some(date) = date {
+            let x = size.x.saturating_sub(date.chars().count()) / 2;
+            printer.with_color(ColorStyle::secondary(), |printer| {
+                printer.print((x, origin.y + digits_height + 1), &date)
+            });
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        // large enough for the smallest digits; the digits grow with the area given (e.g. with
+        // `full_screen()`)
+        Vec2::new(48, 7) // columns, rows (width, height)
+    }
+
+    fn take_focus(&mut self, _source: Direction) -> bool {
+        true
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Char('t') => self.twelve_hour = !self.twelve_hour,
+            Event::Char('s') => {
+                self.seconds = !self.seconds;
+                self.face.set_seconds(self.seconds);
+            }
+            Event::Char('a') => self.analog = !self.analog,
+            _ => return EventResult::Ignored,
+        }
+        EventResult::Consumed(None)
+    }
+}
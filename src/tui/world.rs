@@ -0,0 +1,122 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # World Clock TUI
+//!
+//! ## Expected Behavior
+//!
+//! Shows the local time and the time in each zone, with the offset from UTC, the abbreviation of
+//! the zone, a sun or moon for day or night, and "+1 day"/"-1 day" when the date differs from the
+//! local date. The view takes no input, so it can be put next to other views as a panel.
+
+use crate::world::{day_marker, is_daytime, utc_offset, zone_label};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use cursive::{
+    theme::{BaseColor, Color, ColorStyle, PaletteColor},
+    view::View,
+    Printer, Vec2,
+};
+
+pub struct WorldClockView {
+    zones: Vec<Tz>,
+    show_local: bool,
+}
+
+impl WorldClockView {
+    pub fn new(zones: Vec<Tz>) -> Self {
+        Self {
+            zones,
+            show_local: true,
+        }
+    }
+
+    /// Leaves out the first row, which shows the local time
+    pub fn without_local(mut self) -> Self {
+        self.show_local = false;
+        self
+    }
+
+    fn label_width(&self) -> usize {
+        self.zones
+            .iter()
+            .map(|&zone| zone_label(zone).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8)
+    }
+
+    /// Draws a row with the abbreviation of the zone, if it has one
+    fn draw_row<Z: TimeZone>(
+        &self,
+        printer: &Printer,
+        row: usize,
+        label: &str,
+        time: DateTime<Z>,
+        abbreviation: Option<String>,
+    ) where
+        Z::Offset: std::fmt::Display,
+    {
+        let width = self.label_width();
+        let (icon, color) = if is_daytime(&time) {
+            ("☀", Color::Dark(BaseColor::Yellow))
+        } else {
+            ("☾", Color::Dark(BaseColor::Blue))
+        };
+        printer.print((0, row), label);
+        printer.with_color(ColorStyle::new(color, PaletteColor::View), |printer| {
+            printer.print((width + 2, row), icon)
+        });
+        printer.print((width + 4, row), &time.format("%H:%M:%S").to_string());
+        printer.with_color(ColorStyle::secondary(), |printer| {
+            printer.print(
+                (width + 14, row),
+                &format!(
+                    "{:<9}  {:<5}",
+                    utc_offset(&time),
+                    abbreviation.unwrap_or_default()
+                ),
+            )
+        });
+        printer.print(
+            (width + 32, row),
+            &day_marker(&time, Local::now().date_naive()),
+        );
+    }
+}
+
+impl View for WorldClockView {
+    fn draw(&self, printer: &Printer) {
+        let now = Utc::now();
+        let mut row = 0;
+        if self.show_local {
+            self.draw_row(printer, row, "Local", now.with_timezone(&Local), None);
+            row += 1;
+        }
+        for &zone in &self.zones {
+            let time = now.with_timezone(&zone);
+            let abbreviation = time.format("%Z").to_string();
+            self.draw_row(printer, row, &zone_label(zone), time, Some(abbreviation));
+            row += 1;
+        }
+    }
+
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        let rows = self.zones.len() + self.show_local as usize;
+        Vec2::new(self.label_width() + 39, rows) // columns, rows (width, height)
+    }
+}
@@ -15,6 +15,211 @@
 // You should have received a copy of the GNU General Public License
 // along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
 
-use chrono::Duration;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use hhmmss::Hhmmss;
+use std::path::PathBuf;
 
 pub type BoxedError = Box<dyn std::error::Error>;
+
+/// The directory where clock-cli keeps its data, e.g. `~/.local/share/clock-cli` on Linux.
+/// It can be changed with the `CLOCK_DATA_DIR` environment variable.
+pub fn data_dir() -> PathBuf {
+    match std::env::var_os("CLOCK_DATA_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("clock-cli"),
+    }
+}
+
+/// The directory where clock-cli looks for `config.toml`, e.g. `~/.config/clock-cli` on Linux.
+/// It can be changed with the `CLOCK_CONFIG_DIR` environment variable.
+pub fn config_dir() -> PathBuf {
+    match std::env::var_os("CLOCK_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("clock-cli"),
+    }
+}
+
+/// Parses a local date and time in the form `YYYY-MM-DD HH:MM[:SS]`, `YYYY-MM-DD` (midnight), or
+/// `HH:MM[:SS]` (today)
+pub fn parse_datetime(s: &str) -> Result<DateTime<Local>, String> {
+    let s = s.trim();
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        })
+        .or_else(|_| {
+            NaiveTime::parse_from_str(s, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+                .map(|t| Local::now().naive_local().date().and_time(t))
+        })
+        .map_err(|_| format!("invalid date/time: {}", s))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("invalid local time: {}", s))
+}
+
+/// Checks a `strftime` format such as `%A, %-d %B %Y`, which chrono would otherwise fail to
+/// format with (and panic) if it is invalid
+pub fn check_date_format(s: &str) -> Result<(), String> {
+    if StrftimeItems::new(s).any(|item| item == Item::Error) {
+        Err(format!("invalid date format: {}", s))
+    } else {
+        Ok(())
+    }
+}
+
+/// Parses the duration of a countdown timer: a `humantime` duration such as `1h30m` or `90s`, a
+/// duration with decimals such as `2.5 min`, or `until <time>` (see `parse_datetime`) for the time
+/// left until then. A time of day which has already passed today means tomorrow.
+pub fn parse_timer_duration(s: &str, now: DateTime<Local>) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Some(time) = s.strip_prefix("until ").or_else(|| s.strip_prefix("at ")) {
+        let mut target = parse_datetime(time)?;
+        if target <= now && !time.contains('-') {
+            target += Duration::days(1);
+        }
+        if target <= now {
+            return Err(format!("{} has already passed", time.trim()));
+        }
+        return Ok(target - now);
+    }
+    match s.parse::<humantime::Duration>() {
+        Ok(duration) => Duration::from_std(duration.into()).map_err(|e| e.to_string()),
+        Err(e) => parse_decimal_duration(s).ok_or_else(|| e.to_string()),
+    }
+}
+
+/// Parses a sum of numbers with units, which unlike `humantime` may have decimals, e.g. `2.5 min`
+fn parse_decimal_duration(s: &str) -> Option<Duration> {
+    let mut seconds = 0.0;
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let n: f64 = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_end] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            _ => return None,
+        };
+        seconds += n * unit;
+        rest = rest[unit_end..].trim_start();
+    }
+    Some(Duration::milliseconds((seconds * 1000.0).round() as i64))
+}
+
+/// (De)serializes a `chrono::Duration` as a number of milliseconds
+pub mod duration_ms {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(d.num_milliseconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::milliseconds(i64::deserialize(deserializer)?))
+    }
+}
+
+/// Pretty-prints a (possibly negative) duration in the form `+HH:MM:SS.xxx` or `-HH:MM:SS.xxx`
+///
+/// `Hhmmss::hhmmssxxx` drops the sign of durations shorter than one second, so the sign is handled here.
+pub fn signed_hhmmssxxx(d: Duration) -> String {
+    if d < Duration::zero() {
+        format!("-{}", (-d).hhmmssxxx())
+    } else {
+        format!("+{}", d.hhmmssxxx())
+    }
+}
+
+/// Pretty-prints a duration which may be days long, in the form `3d 04:05:06`, or `04:05:06` when
+/// it is shorter than a day. Negative durations are printed as their absolute value.
+pub fn dhhmmss(d: Duration) -> String {
+    let s = d.num_seconds().abs();
+    let (days, s) = (s / 86400, s % 86400);
+    let hhmmss = format!("{:02}:{:02}:{:02}", s / 3600, s % 3600 / 60, s % 60);
+    if days > 0 {
+        format!("{}d {}", days, hhmmss)
+    } else {
+        hhmmss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_durations() {
+        let now = Local::now();
+        let parse = |s| parse_timer_duration(s, now);
+        assert_eq!(parse("1h30m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse("3 minutes"), Ok(Duration::minutes(3)));
+        assert_eq!(parse(" 90s "), Ok(Duration::seconds(90)));
+        assert_eq!(parse("2.5 min"), Ok(Duration::seconds(150)));
+        assert_eq!(parse("1d 2.5h"), Ok(Duration::seconds(86400 + 9000)));
+        assert_eq!(parse(".5s"), Ok(Duration::milliseconds(500)));
+        assert!(parse("").is_err());
+        assert!(parse("soon").is_err());
+        assert!(parse("2.5 fortnights").is_err());
+    }
+
+    #[test]
+    fn timer_durations_until() {
+        let now = Local::now();
+        let parse = |s: &str| parse_timer_duration(s, now);
+        let target = parse_datetime("2099-01-01 00:00").unwrap();
+        assert_eq!(parse("until 2099-01-01 00:00"), Ok(target - now));
+        assert_eq!(parse("at 2099-01-01"), Ok(target - now));
+        assert!(parse("until 2000-01-01").is_err());
+        assert!(parse("until tomorrow").is_err());
+
+        // a time of day which has passed means tomorrow
+        let passed = (now - Duration::minutes(1)).format("%H:%M").to_string();
+        let left = parse(&format!("until {}", passed)).unwrap();
+        assert!(left > Duration::hours(23) && left <= Duration::hours(24));
+    }
+
+    #[test]
+    fn date_formats() {
+        assert_eq!(check_date_format("%A, %-d %B %Y"), Ok(()));
+        assert_eq!(check_date_format("%Y-%m-%d week %V"), Ok(()));
+        assert_eq!(check_date_format("no fields"), Ok(()));
+        assert!(check_date_format("%Q").is_err());
+        assert!(check_date_format("%Y-%").is_err());
+        assert!(check_date_format("%-").is_err());
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(dhhmmss(Duration::seconds(3723)), "01:02:03");
+        assert_eq!(dhhmmss(Duration::seconds(3 * 86400 + 3723)), "3d 01:02:03");
+        assert_eq!(dhhmmss(Duration::seconds(-59)), "00:00:59");
+        assert_eq!(
+            signed_hhmmssxxx(Duration::milliseconds(-500)),
+            "-00:00:00.500"
+        );
+        assert_eq!(
+            signed_hhmmssxxx(Duration::milliseconds(1500)),
+            "+00:00:01.500"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (C) 2020 Tianyi Shi
+//
+// This file is part of clock-cli-rs.
+//
+// clock-cli-rs is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// clock-cli-rs is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with clock-cli-rs.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # World Clock
+//!
+//! Times in IANA time zones such as `Asia/Tokyo`. The time zone database is bundled (by
+//! `chrono-tz`), so neither a system database nor a network connection is needed. The zones shown
+//! by `clock world` are kept in the config file (see `config`):
+//!
+//! ```toml
+//! zones = ["America/New_York", "Europe/London", "Asia/Kolkata"]
+//! ```
+//!
+//! `clock plan` lays out a day in each of these zones to find a slot within everyone's working
+//! hours.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use std::fmt;
+use std::str::FromStr;
+
+/// Parses an IANA time zone name, ignoring case, e.g. `Europe/London` or `europe/london`
+pub fn parse_zone(s: &str) -> Result<Tz, String> {
+    TZ_VARIANTS
+        .iter()
+        .find(|zone| zone.name().eq_ignore_ascii_case(s.trim()))
+        .copied()
+        .ok_or_else(|| format!("unknown time zone: {} (expected e.g. Europe/London)", s))
+}
+
+/// The city (or the whole name) of a zone, e.g. "New York" for `America/New_York`
+pub fn zone_label(zone: Tz) -> String {
+    let name = zone.name();
+    name.rsplit('/').next().unwrap_or(name).replace('_', " ")
+}
+
+/// The offset from UTC, e.g. "UTC+05:30"
+pub fn utc_offset<Z: TimeZone>(time: &DateTime<Z>) -> String {
+    let seconds = time.offset().fix().local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// Whether it is between 06:00 and 18:00
+pub fn is_daytime<Z: TimeZone>(time: &DateTime<Z>) -> bool {
+    (6..18).contains(&time.hour())
+}
+
+/// "+1 day" or "-1 day" when the date of `time` is after or before `date`
+pub fn day_marker<Z: TimeZone>(time: &DateTime<Z>, date: NaiveDate) -> String {
+    match (time.date_naive() - date).num_days() {
+        0 => String::new(),
+        n if n.abs() == 1 => format!("{:+} day", n),
+        n => format!("{:+} days", n),
+    }
+}
+
+/// Working hours, from `start` to `end` o'clock, Monday to Friday
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorkingHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        Self { start: 9, end: 17 }
+    }
+}
+
+impl WorkingHours {
+    /// Whether `time` is within the working hours in its own time zone
+    pub fn contains<Z: TimeZone>(&self, time: &DateTime<Z>) -> bool {
+        time.weekday().num_days_from_monday() < 5 && (self.start..self.end).contains(&time.hour())
+    }
+}
+
+impl FromStr for WorkingHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("expected working hours like 9-17: {}", s);
+        let (start, end) = s.split_once('-').ok_or_else(err)?;
+        let start: u32 = start.trim().parse().map_err(|_| err())?;
+        let end: u32 = end.trim().parse().map_err(|_| err())?;
+        if start >= end || end > 24 {
+            return Err(err());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for WorkingHours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// The slot from `start` lasting `duration` in the local time zone and in each of `zones`, one
+/// zone per line
+pub fn describe_slot(start: DateTime<Utc>, duration: Duration, zones: &[Tz]) -> String {
+    fn line<Z: TimeZone>(name: &str, start: DateTime<Z>, end: DateTime<Z>) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        format!(
+            "{:<20}  {}–{}  {}",
+            name,
+            start.format("%a %Y-%m-%d %H:%M"),
+            end.format("%H:%M"),
+            utc_offset(&start)
+        )
+    }
+    let end = start + duration;
+    let mut lines = vec![line(
+        "Local",
+        start.with_timezone(&Local),
+        end.with_timezone(&Local),
+    )];
+    for zone in zones {
+        lines.push(line(
+            zone.name(),
+            start.with_timezone(zone),
+            end.with_timezone(zone),
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{America, Asia, Europe, Pacific};
+
+    fn utc(d: u32, h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 10, d, h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn zones() {
+        assert_eq!(parse_zone("Europe/London"), Ok(Europe::London));
+        assert_eq!(parse_zone(" america/new_york "), Ok(America::New_York));
+        assert!(parse_zone("Europe/Atlantis").is_err());
+        assert!(parse_zone("London").is_err());
+        assert!(parse_zone("").is_err());
+        assert_eq!(zone_label(America::New_York), "New York");
+        assert_eq!(zone_label(Tz::UTC), "UTC");
+    }
+
+    #[test]
+    fn offsets() {
+        let t = utc(12, 12, 0);
+        assert_eq!(utc_offset(&t), "UTC+00:00");
+        assert_eq!(utc_offset(&t.with_timezone(&Asia::Kolkata)), "UTC+05:30");
+        assert_eq!(utc_offset(&t.with_timezone(&Asia::Kathmandu)), "UTC+05:45");
+        assert_eq!(
+            utc_offset(&t.with_timezone(&America::St_Johns)),
+            "UTC-02:30"
+        );
+        assert_eq!(
+            utc_offset(&t.with_timezone(&America::New_York)),
+            "UTC-04:00"
+        );
+    }
+
+    #[test]
+    fn day_markers() {
+        let t = utc(12, 23, 30);
+        let today = t.date_naive();
+        assert_eq!(day_marker(&t, today), "");
+        assert_eq!(day_marker(&t.with_timezone(&Asia::Tokyo), today), "+1 day");
+        let t = utc(12, 0, 30);
+        assert_eq!(
+            day_marker(&t.with_timezone(&America::Los_Angeles), today),
+            "-1 day"
+        );
+        // across the date line
+        let t = utc(12, 12, 0).with_timezone(&Pacific::Kiritimati);
+        assert_eq!(day_marker(&t, utc(11, 12, 0).date_naive()), "+2 days");
+        assert!(is_daytime(&utc(12, 12, 0)));
+        assert!(!is_daytime(&utc(12, 18, 0)));
+    }
+
+    #[test]
+    fn working_hours() {
+        assert_eq!("9-17".parse(), Ok(WorkingHours::default()));
+        assert_eq!("8 - 18".parse(), Ok(WorkingHours { start: 8, end: 18 }));
+        assert_eq!("0-24".parse::<WorkingHours>().unwrap().to_string(), "0-24");
+        for s in &["17-9", "9-9", "9-25", "9", "9-", "-17", "9am-5pm", "-1-17"] {
+            assert!(s.parse::<WorkingHours>().is_err(), "{}", s);
+        }
+
+        let hours = WorkingHours::default();
+        // 2020-10-12 was a Monday
+        assert!(hours.contains(&utc(12, 9, 0)));
+        assert!(hours.contains(&utc(12, 16, 59)));
+        assert!(!hours.contains(&utc(12, 17, 0)));
+        assert!(!hours.contains(&utc(12, 8, 59)));
+        assert!(!hours.contains(&utc(17, 12, 0)));
+        // 09:00 in Kolkata is 03:30 UTC
+        assert!(hours.contains(&utc(12, 3, 30).with_timezone(&Asia::Kolkata)));
+        assert!(!hours.contains(&utc(12, 3, 29).with_timezone(&Asia::Kolkata)));
+    }
+}